@@ -0,0 +1,53 @@
+use clap::{Parser, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Server-wide settings, taken from the command line the MCP client uses to
+/// launch the server.
+#[derive(Debug, Clone, Parser)]
+#[command(name = "cargo-mcp", version, about = "MCP server for cargo")]
+pub struct ServerConfig {
+    /// Default timeout in seconds for each cargo invocation (0 disables it)
+    #[arg(long, default_value_t = 600)]
+    pub timeout_secs: u64,
+
+    /// Test runner used by the test tool unless a call picks one
+    #[arg(long, value_enum, default_value_t = TestRunner::Cargo)]
+    pub test_runner: TestRunner,
+
+    /// Local clone of the RustSec advisory database used by the audit tool
+    /// (default: $CARGO_HOME/advisory-db)
+    #[arg(long, value_name = "PATH")]
+    pub advisory_db: Option<PathBuf>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 600,
+            test_runner: TestRunner::Cargo,
+            advisory_db: None,
+        }
+    }
+}
+
+/// Which command runs tests.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestRunner {
+    /// `cargo test`
+    #[default]
+    Cargo,
+    /// `cargo nextest run`, falling back to `cargo test` when it is not
+    /// installed
+    Nextest,
+}
+
+impl TestRunner {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Cargo => "cargo",
+            Self::Nextest => "nextest",
+        }
+    }
+}
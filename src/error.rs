@@ -18,6 +18,14 @@ impl McpError {
         }
     }
 
+    pub fn invalid_request(message: String) -> Self {
+        Self {
+            code: -32600,
+            message,
+            data: None,
+        }
+    }
+
     pub fn method_not_found(method: String) -> Self {
         Self {
             code: -32601,
@@ -34,6 +42,17 @@ impl McpError {
         }
     }
 
+    pub fn unsupported_protocol_version(requested: Option<&str>, supported: &[&str]) -> Self {
+        Self {
+            code: -32602,
+            message: "Unsupported protocol version".to_string(),
+            data: Some(serde_json::json!({
+                "supported": supported,
+                "requested": requested
+            })),
+        }
+    }
+
     pub fn internal_error(message: String) -> Self {
         Self {
             code: -32603,
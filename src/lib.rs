@@ -1,9 +1,13 @@
+pub mod config;
 pub mod error;
+pub mod protocol;
 pub mod server;
 pub mod tools;
 pub mod types;
 
+pub use config::*;
 pub use error::*;
+pub use protocol::*;
 pub use server::*;
 pub use tools::*;
 pub use types::*;
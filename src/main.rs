@@ -1,7 +1,9 @@
 use anyhow::Result;
+use cargo_mcp::config::ServerConfig;
 use cargo_mcp::server::CargoMcpServer;
+use clap::Parser;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    CargoMcpServer::run().await
+    CargoMcpServer::run(ServerConfig::parse()).await
 }
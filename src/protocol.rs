@@ -0,0 +1,142 @@
+use std::fmt;
+
+/// MCP specification revisions this server implements, oldest first.
+///
+/// Clients that never negotiate get the revision the server originally
+/// shipped with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ProtocolVersion {
+    #[default]
+    V2024_11_05,
+    V2025_03_26,
+    V2025_06_18,
+}
+
+impl ProtocolVersion {
+    pub const ALL: [ProtocolVersion; 3] = [
+        ProtocolVersion::V2024_11_05,
+        ProtocolVersion::V2025_03_26,
+        ProtocolVersion::V2025_06_18,
+    ];
+
+    pub const LATEST: ProtocolVersion = ProtocolVersion::V2025_06_18;
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ProtocolVersion::V2024_11_05 => "2024-11-05",
+            ProtocolVersion::V2025_03_26 => "2025-03-26",
+            ProtocolVersion::V2025_06_18 => "2025-06-18",
+        }
+    }
+
+    /// Pick the newest version both sides support for the version a client
+    /// requested in `initialize`.
+    ///
+    /// Revisions are dated, so a client asking for a revision newer than any
+    /// we know gets our latest one, which it is expected to also speak.
+    /// Anything that is not a revision date, or predates every revision we
+    /// implement, has no common version and yields `None`.
+    pub fn negotiate(requested: &str) -> Option<ProtocolVersion> {
+        if !is_revision_date(requested) {
+            return None;
+        }
+        Self::ALL
+            .into_iter()
+            .rev()
+            .find(|version| version.as_str() <= requested)
+    }
+
+    /// `structuredContent` in tool results and `outputSchema` on tools.
+    pub fn supports_structured_output(self) -> bool {
+        self >= ProtocolVersion::V2025_06_18
+    }
+
+    /// Behavior hints (`annotations`) on tools.
+    pub fn supports_tool_annotations(self) -> bool {
+        self >= ProtocolVersion::V2025_03_26
+    }
+
+    /// A human-readable `message` on progress notifications.
+    pub fn supports_progress_messages(self) -> bool {
+        self >= ProtocolVersion::V2025_03_26
+    }
+
+    /// JSON-RPC batches were added in 2025-03-26 and removed again in
+    /// 2025-06-18.
+    pub fn supports_batching(self) -> bool {
+        self == ProtocolVersion::V2025_03_26
+    }
+}
+
+impl fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// `YYYY-MM-DD`, the format of every MCP revision identifier.
+fn is_revision_date(version: &str) -> bool {
+    let bytes = version.as_bytes();
+    bytes.len() == 10
+        && bytes.iter().enumerate().all(|(i, b)| match i {
+            4 | 7 => *b == b'-',
+            _ => b.is_ascii_digit(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negotiates_known_revisions_exactly() {
+        for version in ProtocolVersion::ALL {
+            assert_eq!(ProtocolVersion::negotiate(version.as_str()), Some(version));
+        }
+    }
+
+    #[test]
+    fn negotiates_the_newest_revision_not_after_the_request() {
+        assert_eq!(
+            ProtocolVersion::negotiate("2025-01-01"),
+            Some(ProtocolVersion::V2024_11_05)
+        );
+        assert_eq!(
+            ProtocolVersion::negotiate("2025-06-17"),
+            Some(ProtocolVersion::V2025_03_26)
+        );
+        assert_eq!(
+            ProtocolVersion::negotiate("2099-12-31"),
+            Some(ProtocolVersion::LATEST)
+        );
+    }
+
+    #[test]
+    fn rejects_old_revisions_and_other_strings() {
+        assert_eq!(ProtocolVersion::negotiate("2024-11-04"), None);
+        assert_eq!(ProtocolVersion::negotiate("2024-10-07"), None);
+        assert_eq!(ProtocolVersion::negotiate(""), None);
+        assert_eq!(ProtocolVersion::negotiate("latest"), None);
+        assert_eq!(ProtocolVersion::negotiate("2025-6-18"), None);
+        assert_eq!(ProtocolVersion::negotiate("2025/06/18"), None);
+        assert_eq!(ProtocolVersion::negotiate("2025-06-18T00:00"), None);
+        assert_eq!(ProtocolVersion::negotiate("v2025-06-1"), None);
+    }
+
+    #[test]
+    fn gates_features_by_revision() {
+        use ProtocolVersion::*;
+        let features = |v: ProtocolVersion| {
+            (
+                v.supports_tool_annotations(),
+                v.supports_progress_messages(),
+                v.supports_batching(),
+                v.supports_structured_output(),
+            )
+        };
+        assert_eq!(features(V2024_11_05), (false, false, false, false));
+        assert_eq!(features(V2025_03_26), (true, true, true, false));
+        assert_eq!(features(V2025_06_18), (true, true, false, true));
+        assert_eq!(ProtocolVersion::default(), V2024_11_05);
+    }
+}
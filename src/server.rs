@@ -1,31 +1,81 @@
 use anyhow::Result;
-use serde_json::json;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader as AsyncBufReader};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 
+use crate::config::ServerConfig;
 use crate::error::McpError;
+use crate::protocol::ProtocolVersion;
+use crate::tools::context::CancellationToken;
+use crate::tools::progress::ProgressReporter;
+use crate::tools::state::ToolState;
+use crate::tools::workspace_queue::{QueueTicket, WorkspaceQueue, is_mutating_tool, workspace_key};
 use crate::tools::{get_available_tools, handle_tool_call};
-use crate::types::{McpRequest, McpResponse};
+use crate::types::{McpMessage, McpRequest, McpResponse};
 
-pub struct CargoMcpServer;
+pub struct CargoMcpServer {
+    config: ServerConfig,
+    workspace_queue: WorkspaceQueue,
+    /// Results remembered between tool calls, such as failed tests.
+    tool_state: ToolState,
+    /// Cancellation tokens of running tool calls, keyed by JSON request id.
+    in_flight: Mutex<HashMap<String, CancellationToken>>,
+    /// Revision agreed on in `initialize`.
+    protocol_version: Mutex<ProtocolVersion>,
+}
 
 impl CargoMcpServer {
-    fn handle_request(request: McpRequest) -> McpResponse {
+    async fn handle_request(
+        &self,
+        request: McpRequest,
+        cancellation: CancellationToken,
+        outgoing: &mpsc::UnboundedSender<McpMessage>,
+    ) -> McpResponse {
         match request.method.as_str() {
-            "initialize" => McpResponse {
-                jsonrpc: "2.0".to_string(),
-                id: request.id,
-                result: Some(json!({
-                    "protocolVersion": "2024-11-05",
-                    "capabilities": {
-                        "tools": {}
-                    },
-                    "serverInfo": {
-                        "name": "cargo-mcp",
-                        "version": "0.1.0"
+            "initialize" => {
+                let requested = request
+                    .params
+                    .as_ref()
+                    .and_then(|params| params.get("protocolVersion"))
+                    .and_then(|v| v.as_str());
+                match requested.and_then(ProtocolVersion::negotiate) {
+                    Some(version) => {
+                        *self.protocol_version() = version;
+                        McpResponse {
+                            jsonrpc: "2.0".to_string(),
+                            id: request.id,
+                            result: Some(json!({
+                                "protocolVersion": version.as_str(),
+                                "capabilities": {
+                                    "tools": {}
+                                },
+                                "serverInfo": {
+                                    "name": "cargo-mcp",
+                                    "version": "0.1.0"
+                                }
+                            })),
+                            error: None,
+                        }
                     }
-                })),
-                error: None,
-            },
+                    None => {
+                        let supported: Vec<&str> = ProtocolVersion::ALL
+                            .iter()
+                            .map(|version| version.as_str())
+                            .collect();
+                        McpResponse {
+                            jsonrpc: "2.0".to_string(),
+                            id: request.id,
+                            result: None,
+                            error: Some(McpError::unsupported_protocol_version(
+                                requested, &supported,
+                            )),
+                        }
+                    }
+                }
+            }
             "notifications/initialized" => {
                 // Just ignore this notification - return empty response
                 McpResponse {
@@ -35,34 +85,91 @@ impl CargoMcpServer {
                     error: None,
                 }
             }
-            "tools/list" => McpResponse {
+            "notifications/cancelled" => {
+                if let Some(request_id) = request
+                    .params
+                    .as_ref()
+                    .and_then(|params| params.get("requestId"))
+                {
+                    self.cancel_in_flight(request_id);
+                }
+                McpResponse {
+                    jsonrpc: "2.0".to_string(),
+                    id: request.id,
+                    result: None,
+                    error: None,
+                }
+            }
+            "ping" => McpResponse {
                 jsonrpc: "2.0".to_string(),
                 id: request.id,
-                result: Some(json!({
-                    "tools": get_available_tools()
-                })),
+                result: Some(json!({})),
                 error: None,
             },
+            "tools/list" => {
+                let mut tools = get_available_tools();
+                let version = self.negotiated_version();
+                for tool in &mut tools {
+                    if !version.supports_tool_annotations() {
+                        tool.annotations = None;
+                    }
+                    if !version.supports_structured_output() {
+                        tool.output_schema = None;
+                    }
+                }
+                McpResponse {
+                    jsonrpc: "2.0".to_string(),
+                    id: request.id,
+                    result: Some(json!({
+                        "tools": tools
+                    })),
+                    error: None,
+                }
+            }
             "tools/call" => {
                 if let Some(params) = request.params {
                     if let (Some(tool_name), Some(arguments)) = (
                         params.get("name").and_then(|v| v.as_str()),
                         params.get("arguments"),
                     ) {
-                        match handle_tool_call(tool_name, arguments.clone()) {
-                            Ok(result) => McpResponse {
-                                jsonrpc: "2.0".to_string(),
-                                id: request.id,
-                                result: Some(result),
-                                error: None,
-                            },
-                            Err(e) => McpResponse {
+                        let progress = params
+                            .get("_meta")
+                            .and_then(|meta| meta.get("progressToken"))
+                            .map(|token| {
+                                ProgressReporter::new(
+                                    token.clone(),
+                                    self.negotiated_version(),
+                                    outgoing.clone(),
+                                )
+                            });
+                        match handle_tool_call(
+                            tool_name,
+                            arguments.clone(),
+                            &self.config,
+                            &self.tool_state,
+                            cancellation,
+                            progress,
+                        )
+                        .await
+                        {
+                            Ok(mut result) => {
+                                if !self.negotiated_version().supports_structured_output()
+                                    && let Some(result) = result.as_object_mut()
+                                {
+                                    result.remove("structuredContent");
+                                }
+                                McpResponse {
+                                    jsonrpc: "2.0".to_string(),
+                                    id: request.id,
+                                    result: Some(result),
+                                    error: None,
+                                }
+                            }
+                            Err(error) => McpResponse {
                                 jsonrpc: "2.0".to_string(),
                                 id: request.id,
                                 result: None,
-                                error: Some(McpError::internal_error(format!(
-                                    "Tool execution failed: {e}"
-                                ))),
+                                error: Some(error),
                             },
                         }
                     } else {
@@ -95,9 +202,183 @@ impl CargoMcpServer {
         }
     }
 
-    pub async fn run() -> Result<()> {
+    /// Reserve a place in the workspace queue for calls that mutate the
+    /// workspace. Must be called in the order requests are read.
+    fn queue_ticket(&self, request: &McpRequest) -> Option<QueueTicket> {
+        if request.method != "tools/call" {
+            return None;
+        }
+        let params = request.params.as_ref()?;
+        let tool_name = params.get("name")?.as_str()?;
+        if !is_mutating_tool(tool_name) {
+            return None;
+        }
+        let working_directory = params
+            .get("arguments")
+            .and_then(|arguments| arguments.get("working_directory"))
+            .and_then(|v| v.as_str());
+        Some(
+            self.workspace_queue
+                .enqueue(workspace_key(working_directory)),
+        )
+    }
+
+    fn in_flight(&self) -> MutexGuard<'_, HashMap<String, CancellationToken>> {
+        self.in_flight
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Register a tool call so a later `notifications/cancelled` can abort it.
+    fn track_in_flight(&self, request: &McpRequest) -> CancellationToken {
+        let cancellation = CancellationToken::default();
+        if request.method == "tools/call"
+            && let Some(id) = &request.id
+        {
+            self.in_flight()
+                .insert(id.to_string(), cancellation.clone());
+        }
+        cancellation
+    }
+
+    fn finish_in_flight(&self, request_id: Option<&Value>) {
+        if let Some(id) = request_id {
+            self.in_flight().remove(&id.to_string());
+        }
+    }
+
+    fn cancel_in_flight(&self, request_id: &Value) {
+        if let Some(cancellation) = self.in_flight().get(&request_id.to_string()) {
+            cancellation.cancel();
+        }
+    }
+
+    fn protocol_version(&self) -> MutexGuard<'_, ProtocolVersion> {
+        self.protocol_version
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn negotiated_version(&self) -> ProtocolVersion {
+        *self.protocol_version()
+    }
+
+    /// Handle one request on its own task. Resolves to the response to send,
+    /// or `None` for notifications and cancelled calls.
+    fn spawn_request(
+        self: &Arc<Self>,
+        request: McpRequest,
+        responses: &mpsc::UnboundedSender<McpMessage>,
+    ) -> JoinHandle<Option<McpResponse>> {
+        let ticket = self.queue_ticket(&request);
+        let cancellation = self.track_in_flight(&request);
+        let server = Arc::clone(self);
+        let responses = responses.clone();
+        tokio::spawn(async move {
+            let _guard = match ticket {
+                Some(ticket) => Some(ticket.acquire().await),
+                None => None,
+            };
+            let request_id = request.id.clone();
+            let response = server
+                .handle_request(request, cancellation.clone(), &responses)
+                .await;
+            server.finish_in_flight(request_id.as_ref());
+
+            // The client no longer expects an answer to a cancelled call
+            if cancellation.is_cancelled() {
+                return None;
+            }
+
+            // Only send response if it has content (skip notifications)
+            (response.result.is_some() || response.error.is_some()).then_some(response)
+        })
+    }
+
+    fn error_response(id: Option<Value>, error: McpError) -> McpResponse {
+        McpResponse {
+            jsonrpc: "2.0".to_string(),
+            id,
+            result: None,
+            error: Some(error),
+        }
+    }
+
+    /// Dispatch every request of a JSON-RPC batch and answer with a single
+    /// array once all of them are done.
+    fn spawn_batch(
+        self: &Arc<Self>,
+        batch: Vec<Value>,
+        responses: &mpsc::UnboundedSender<McpMessage>,
+    ) {
+        if !self.negotiated_version().supports_batching() {
+            let _ = responses.send(McpMessage::Response(Self::error_response(
+                None,
+                McpError::invalid_request(format!(
+                    "JSON-RPC batches are not supported in protocol version {}",
+                    self.negotiated_version()
+                )),
+            )));
+            return;
+        }
+        if batch.is_empty() {
+            let _ = responses.send(McpMessage::Response(Self::error_response(
+                None,
+                McpError::invalid_request("Empty batch".to_string()),
+            )));
+            return;
+        }
+
+        let mut pending = Vec::new();
+        let mut invalid = Vec::new();
+        for entry in batch {
+            match serde_json::from_value::<McpRequest>(entry) {
+                Ok(request) => pending.push(self.spawn_request(request, responses)),
+                Err(e) => invalid.push(Self::error_response(
+                    None,
+                    McpError::invalid_request(format!("Invalid request: {e}")),
+                )),
+            }
+        }
+
+        let responses = responses.clone();
+        tokio::spawn(async move {
+            let mut batch_responses = invalid;
+            for handle in pending {
+                if let Ok(Some(response)) = handle.await {
+                    batch_responses.push(response);
+                }
+            }
+            if !batch_responses.is_empty() {
+                let _ = responses.send(McpMessage::Batch(batch_responses));
+            }
+        });
+    }
+
+    pub async fn run(config: ServerConfig) -> Result<()> {
+        let server = Arc::new(Self {
+            config,
+            workspace_queue: WorkspaceQueue::default(),
+            tool_state: ToolState::default(),
+            in_flight: Mutex::new(HashMap::new()),
+            protocol_version: Mutex::new(ProtocolVersion::default()),
+        });
+
+        // Responses and notifications go through one writer task so lines never
+        // interleave.
+        let (responses, mut outgoing) = mpsc::unbounded_channel::<McpMessage>();
+        let writer = tokio::spawn(async move {
+            let mut stdout = tokio::io::stdout();
+            while let Some(response) = outgoing.recv().await {
+                let response_json = serde_json::to_string(&response)?;
+                stdout.write_all(response_json.as_bytes()).await?;
+                stdout.write_all(b"\n").await?;
+                stdout.flush().await?;
+            }
+            Ok::<(), anyhow::Error>(())
+        });
+
         let stdin = tokio::io::stdin();
-        let mut stdout = tokio::io::stdout();
         let mut reader = AsyncBufReader::new(stdin);
         let mut line = String::new();
 
@@ -112,29 +393,42 @@ impl CargoMcpServer {
                         continue;
                     }
 
-                    match serde_json::from_str::<McpRequest>(trimmed) {
-                        Ok(request) => {
-                            let response = Self::handle_request(request);
-
-                            // Only send response if it has content (skip notifications)
-                            if response.result.is_some() || response.error.is_some() {
-                                let response_json = serde_json::to_string(&response)?;
-                                stdout.write_all(response_json.as_bytes()).await?;
-                                stdout.write_all(b"\n").await?;
-                                stdout.flush().await?;
+                    match serde_json::from_str::<Value>(trimmed) {
+                        Ok(Value::Array(batch)) => server.spawn_batch(batch, &responses),
+                        Ok(message) => match serde_json::from_value::<McpRequest>(message) {
+                            // Negotiation decides how every later request is
+                            // handled, so finish it before reading the next line.
+                            Ok(request) if request.method == "initialize" => {
+                                let response = server
+                                    .handle_request(
+                                        request,
+                                        CancellationToken::default(),
+                                        &responses,
+                                    )
+                                    .await;
+                                let _ = responses.send(McpMessage::Response(response));
                             }
-                        }
+                            Ok(request) => {
+                                let handle = server.spawn_request(request, &responses);
+                                let responses = responses.clone();
+                                tokio::spawn(async move {
+                                    if let Ok(Some(response)) = handle.await {
+                                        let _ = responses.send(McpMessage::Response(response));
+                                    }
+                                });
+                            }
+                            Err(e) => {
+                                let _ = responses.send(McpMessage::Response(Self::error_response(
+                                    None,
+                                    McpError::invalid_request(format!("Invalid request: {e}")),
+                                )));
+                            }
+                        },
                         Err(e) => {
-                            let error_response = McpResponse {
-                                jsonrpc: "2.0".to_string(),
-                                id: None,
-                                result: None,
-                                error: Some(McpError::parse_error(format!("Parse error: {e}"))),
-                            };
-                            let response_json = serde_json::to_string(&error_response)?;
-                            stdout.write_all(response_json.as_bytes()).await?;
-                            stdout.write_all(b"\n").await?;
-                            stdout.flush().await?;
+                            let _ = responses.send(McpMessage::Response(Self::error_response(
+                                None,
+                                McpError::parse_error(format!("Parse error: {e}")),
+                            )));
                         }
                     }
                 }
@@ -145,6 +439,9 @@ impl CargoMcpServer {
             }
         }
 
-        Ok(())
+        // In-flight requests still hold senders; the writer drains their
+        // responses before it stops.
+        drop(responses);
+        writer.await?
     }
 }
@@ -0,0 +1,505 @@
+use anyhow::{Context, Result};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use super::dependency_graph::{DependencyGraph, EdgeKinds, GraphEdge};
+use super::registry_index::{IndexVersion, RegistryIndex};
+
+/// Dependency paths reported per finding.
+const PATH_LIMIT: usize = 5;
+
+#[derive(Debug, Deserialize)]
+struct AdvisoryFile {
+    advisory: AdvisoryMetadata,
+    #[serde(default)]
+    versions: Option<AdvisoryVersions>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdvisoryMetadata {
+    id: String,
+    package: String,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    date: Option<String>,
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    aliases: Vec<String>,
+    /// `unmaintained`, `unsound` or `notice` for advisories that are not
+    /// vulnerabilities.
+    #[serde(default)]
+    informational: Option<String>,
+    #[serde(default)]
+    withdrawn: Option<toml::Value>,
+    // Layout of the database before 2021, kept by older checkouts.
+    #[serde(default)]
+    patched_versions: Vec<String>,
+    #[serde(default)]
+    unaffected_versions: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdvisoryVersions {
+    #[serde(default)]
+    patched: Vec<String>,
+    #[serde(default)]
+    unaffected: Vec<String>,
+}
+
+/// One RustSec advisory.
+#[derive(Debug)]
+pub struct Advisory {
+    pub id: String,
+    pub package: String,
+    pub title: Option<String>,
+    pub date: Option<String>,
+    pub url: Option<String>,
+    pub aliases: Vec<String>,
+    pub informational: Option<String>,
+    pub patched: Vec<String>,
+    pub unaffected: Vec<String>,
+}
+
+impl Advisory {
+    /// Parse an advisory file: Markdown with a fenced TOML block, or a bare
+    /// TOML file from older checkouts. The Markdown heading is the title.
+    fn parse(contents: &str) -> Result<Option<Self>> {
+        let (front_matter, body) = match contents.trim_start().strip_prefix("```toml") {
+            Some(rest) => rest.split_once("```").context("Unterminated TOML block")?,
+            None => (contents, ""),
+        };
+        let file: AdvisoryFile = toml::from_str(front_matter)?;
+        if file.advisory.withdrawn.is_some() {
+            return Ok(None);
+        }
+
+        let title = file.advisory.title.or_else(|| {
+            body.lines()
+                .find_map(|line| line.strip_prefix("# "))
+                .map(|title| title.trim().to_string())
+        });
+        let (patched, unaffected) = match file.versions {
+            Some(versions) => (versions.patched, versions.unaffected),
+            None => (
+                file.advisory.patched_versions,
+                file.advisory.unaffected_versions,
+            ),
+        };
+        Ok(Some(Self {
+            id: file.advisory.id,
+            package: file.advisory.package,
+            title,
+            date: file.advisory.date,
+            url: file.advisory.url,
+            aliases: file.advisory.aliases,
+            informational: file.advisory.informational,
+            patched,
+            unaffected,
+        }))
+    }
+
+    fn requirements(reqs: &[String]) -> Vec<VersionReq> {
+        reqs.iter()
+            .filter_map(|req| VersionReq::parse(req).ok())
+            .collect()
+    }
+
+    pub fn is_patched(&self, version: &Version) -> bool {
+        Self::requirements(&self.patched)
+            .iter()
+            .any(|req| req.matches(version))
+    }
+
+    /// A version is affected unless it is patched or listed as unaffected.
+    pub fn affects(&self, version: &Version) -> bool {
+        !self.is_patched(version)
+            && !Self::requirements(&self.unaffected)
+                .iter()
+                .any(|req| req.matches(version))
+    }
+
+    /// The affected range in words: everything outside the patched and
+    /// unaffected requirements.
+    pub fn affected_versions(&self) -> String {
+        let safe: Vec<&str> = self
+            .patched
+            .iter()
+            .chain(&self.unaffected)
+            .map(String::as_str)
+            .collect();
+        if safe.is_empty() {
+            "all versions".to_string()
+        } else {
+            format!("all versions except {}", safe.join(" or "))
+        }
+    }
+}
+
+/// Advisories for the given crate names from a local advisory-db checkout,
+/// which keeps one directory per crate under `crates/`.
+pub fn load_advisories(db: &Path, names: &BTreeSet<&str>) -> Result<Vec<Advisory>> {
+    let crates = db.join("crates");
+    if !crates.is_dir() {
+        anyhow::bail!("{} has no crates directory", db.display());
+    }
+
+    let mut advisories = Vec::new();
+    for name in names {
+        let Ok(entries) = std::fs::read_dir(crates.join(name)) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path
+                .extension()
+                .is_some_and(|extension| extension == "md" || extension == "toml")
+            {
+                continue;
+            }
+            let contents = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let advisory = Advisory::parse(&contents)
+                .with_context(|| format!("Failed to parse {}", path.display()))?;
+            advisories.extend(advisory);
+        }
+    }
+    advisories.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(advisories)
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditFinding {
+    pub id: String,
+    /// `vulnerability`, or the informational kind such as `unmaintained`.
+    pub kind: String,
+    pub package: String,
+    pub version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    pub affected_versions: String,
+    pub patched_versions: Vec<String>,
+    pub unaffected_versions: Vec<String>,
+    /// Paths from the workspace members to the package, as `name@version`.
+    pub paths: Vec<Vec<String>>,
+    pub fix: AuditFix,
+}
+
+/// How to move off an affected version, as calls to this server's tools.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case", tag = "action")]
+pub enum AuditFix {
+    /// A patched release satisfies every requirement on the package, so
+    /// only Cargo.lock changes: arguments for the `update` tool.
+    Update { arguments: Value, message: String },
+    /// Workspace members require an affected version themselves:
+    /// arguments for one `add_crate` call per member.
+    AddCrate {
+        arguments: Vec<Value>,
+        message: String,
+    },
+    /// Dependencies outside the workspace require an affected version.
+    UpgradeDependents {
+        blocked_by: Vec<String>,
+        message: String,
+    },
+    /// No usable patched release is known.
+    Replace { message: String },
+}
+
+impl AuditFix {
+    fn message(&self) -> &str {
+        match self {
+            AuditFix::Update { message, .. }
+            | AuditFix::AddCrate { message, .. }
+            | AuditFix::UpgradeDependents { message, .. }
+            | AuditFix::Replace { message } => message,
+        }
+    }
+}
+
+/// Check every resolved registry package against the advisories. The
+/// index cache, when available, is used to pick the patched release to
+/// move to.
+pub fn audit(
+    graph: &DependencyGraph,
+    advisories: &[Advisory],
+    mut index: Option<&mut RegistryIndex>,
+) -> Vec<AuditFinding> {
+    let mut findings = Vec::new();
+    for node in &graph.nodes {
+        let Some(source) = node
+            .source
+            .as_deref()
+            .filter(|source| source.starts_with("registry+") || source.starts_with("sparse+"))
+        else {
+            continue;
+        };
+        let Ok(version) = Version::parse(&node.version) else {
+            continue;
+        };
+        for advisory in advisories {
+            if advisory.package != node.name || !advisory.affects(&version) {
+                continue;
+            }
+
+            let (paths, _) = graph.paths_to(&node.label(), EdgeKinds::default(), PATH_LIMIT);
+            let paths = paths
+                .iter()
+                .map(|path| path.iter().map(|id| graph.label(id)).collect())
+                .collect();
+            let published = index
+                .as_deref_mut()
+                .and_then(|index| index.versions(source, &node.name));
+            let fix = plan_fix(graph, &node.id, advisory, published);
+
+            findings.push(AuditFinding {
+                id: advisory.id.clone(),
+                kind: advisory
+                    .informational
+                    .clone()
+                    .unwrap_or_else(|| "vulnerability".to_string()),
+                package: node.name.clone(),
+                version: node.version.clone(),
+                title: advisory.title.clone(),
+                date: advisory.date.clone(),
+                url: advisory.url.clone(),
+                aliases: advisory.aliases.clone(),
+                affected_versions: advisory.affected_versions(),
+                patched_versions: advisory.patched.clone(),
+                unaffected_versions: advisory.unaffected.clone(),
+                paths,
+                fix,
+            });
+        }
+    }
+    findings
+}
+
+/// Pick the patched release to move to: the newest one every dependent
+/// accepts is an `update`; otherwise the newest one overall, which needs
+/// workspace requirements raised or other dependents upgraded.
+fn plan_fix(
+    graph: &DependencyGraph,
+    id: &str,
+    advisory: &Advisory,
+    published: Option<&[IndexVersion]>,
+) -> AuditFix {
+    let name = &advisory.package;
+    if advisory.patched.is_empty() {
+        return AuditFix::Replace {
+            message: format!(
+                "{name} has no patched release; remove it or switch to an alternative"
+            ),
+        };
+    }
+    let patched = advisory.patched.join(" or ");
+    let Some(published) = published else {
+        return AuditFix::Update {
+            arguments: json!({ "package": graph.label(id) }),
+            message: format!(
+                "{name} is not in the local index cache; update it to a release matching {patched}"
+            ),
+        };
+    };
+
+    let dependents: Vec<_> = graph.edges_to(id).collect();
+    let accepts = |edge: &&GraphEdge, candidate: &Version| {
+        edge.req
+            .iter()
+            .filter_map(|req| VersionReq::parse(req).ok())
+            .all(|req| req.matches(candidate))
+    };
+    let candidates: Vec<&Version> = published
+        .iter()
+        .filter(|candidate| !candidate.yanked && candidate.version.pre.is_empty())
+        .map(|candidate| &candidate.version)
+        .filter(|candidate| advisory.is_patched(candidate))
+        .collect();
+
+    if let Some(target) = candidates
+        .iter()
+        .copied()
+        .filter(|candidate| dependents.iter().all(|edge| accepts(edge, candidate)))
+        .max()
+    {
+        return AuditFix::Update {
+            arguments: json!({ "package": graph.label(id), "precise": target.to_string() }),
+            message: format!("Update {name} to {target}, which every dependent accepts"),
+        };
+    }
+    let Some(newest) = candidates.into_iter().max() else {
+        return AuditFix::Replace {
+            message: format!("The local index cache has no release of {name} matching {patched}"),
+        };
+    };
+
+    // `add_crate` can only rewrite a plain `[dependencies]` entry; other
+    // requirements in members have to be edited by hand.
+    let (members, others): (Vec<&&GraphEdge>, Vec<&&GraphEdge>) = dependents
+        .iter()
+        .filter(|edge| !accepts(edge, newest))
+        .partition(|edge| {
+            edge.name == *name
+                && edge
+                    .kinds
+                    .iter()
+                    .all(|kind| kind.kind() == "normal" && kind.target.is_none())
+                && graph
+                    .nodes
+                    .iter()
+                    .any(|node| node.id == edge.from && node.workspace_member)
+        });
+    // Requirements take the release without build metadata.
+    let newest = Version::new(newest.major, newest.minor, newest.patch);
+    if others.is_empty() {
+        let mut arguments: Vec<Value> = members
+            .iter()
+            .filter_map(|edge| graph.nodes.iter().find(|node| node.id == edge.from))
+            .map(|member| json!({ "crate_name": format!("{name}@{newest}"), "package": member.name }))
+            .collect();
+        arguments.dedup();
+        return AuditFix::AddCrate {
+            arguments,
+            message: format!(
+                "Raise the {name} requirement to {newest} in the workspace members that declare it"
+            ),
+        };
+    }
+
+    let mut blocked_by: Vec<String> = others.iter().map(|edge| graph.label(&edge.from)).collect();
+    blocked_by.sort();
+    blocked_by.dedup();
+    let message = format!(
+        "{} require{} an affected version of {name}; raise those requirements or upgrade to releases that accept {newest}",
+        blocked_by.join(", "),
+        if blocked_by.len() == 1 { "s" } else { "" }
+    );
+    AuditFix::UpgradeDependents {
+        blocked_by,
+        message,
+    }
+}
+
+impl AuditFinding {
+    pub fn summary(&self) -> String {
+        let mut text = format!("{} {}@{}", self.id, self.package, self.version);
+        if let Some(title) = &self.title {
+            text.push_str(&format!(": {title}"));
+        }
+        if self.kind != "vulnerability" {
+            text.push_str(&format!(" ({})", self.kind));
+        }
+        text.push_str(&format!("\n  affected: {}", self.affected_versions));
+        for path in &self.paths {
+            text.push_str(&format!("\n  path: {}", path.join(" -> ")));
+        }
+        text.push_str(&format!("\n  fix: {}", self.fix.message()));
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An advisory in the current advisory-db layout: TOML front matter in
+    /// a Markdown file, titled by its first heading.
+    const MARKDOWN_ADVISORY: &str = include_str!("testdata/RUSTSEC-2021-0124.md");
+
+    /// An advisory in the pre-2021 layout: a bare TOML file with the version
+    /// ranges under `[advisory]`.
+    const LEGACY_ADVISORY: &str = include_str!("testdata/RUSTSEC-2018-0003.toml");
+
+    fn version(version: &str) -> Version {
+        Version::parse(version).unwrap()
+    }
+
+    #[test]
+    fn parses_markdown_advisories() {
+        let advisory = Advisory::parse(MARKDOWN_ADVISORY).unwrap().unwrap();
+        assert_eq!(advisory.id, "RUSTSEC-2021-0124");
+        assert_eq!(advisory.package, "tokio");
+        assert_eq!(
+            advisory.title.as_deref(),
+            Some("Data race when sending and receiving after closing a `oneshot` channel")
+        );
+        assert_eq!(advisory.date.as_deref(), Some("2021-11-16"));
+        assert_eq!(advisory.aliases, ["CVE-2021-45710", "GHSA-fg7r-2g4j-5cgr"]);
+        assert_eq!(advisory.informational, None);
+        assert_eq!(advisory.patched, [">= 1.8.4, < 1.9.0", ">= 1.13.1"]);
+        assert_eq!(advisory.unaffected, ["< 0.1.14"]);
+        assert_eq!(
+            advisory.affected_versions(),
+            "all versions except >= 1.8.4, < 1.9.0 or >= 1.13.1 or < 0.1.14"
+        );
+    }
+
+    #[test]
+    fn parses_legacy_toml_advisories() {
+        let advisory = Advisory::parse(LEGACY_ADVISORY).unwrap().unwrap();
+        assert_eq!(advisory.id, "RUSTSEC-2018-0003");
+        assert_eq!(advisory.package, "smallvec");
+        assert_eq!(
+            advisory.title.as_deref(),
+            Some("Possible double free during unwinding in SmallVec::insert_many")
+        );
+        assert_eq!(advisory.patched, [">= 0.6.3", "^0.3.4", "^0.4.5", "^0.5.1"]);
+        assert_eq!(advisory.unaffected, ["< 0.3.2"]);
+    }
+
+    #[test]
+    fn checks_affected_versions() {
+        let tokio = Advisory::parse(MARKDOWN_ADVISORY).unwrap().unwrap();
+        assert!(tokio.affects(&version("1.13.0")));
+        assert!(tokio.affects(&version("1.9.0")));
+        assert!(!tokio.affects(&version("1.8.4")));
+        assert!(!tokio.affects(&version("1.20.1")));
+        assert!(!tokio.affects(&version("0.1.13")));
+        assert!(!tokio.is_patched(&version("0.1.13")));
+
+        let smallvec = Advisory::parse(LEGACY_ADVISORY).unwrap().unwrap();
+        assert!(smallvec.affects(&version("0.6.2")));
+        assert!(smallvec.affects(&version("0.4.4")));
+        assert!(!smallvec.affects(&version("0.4.5")));
+        assert!(!smallvec.affects(&version("0.3.1")));
+    }
+
+    #[test]
+    fn skips_withdrawn_advisories() {
+        let withdrawn = MARKDOWN_ADVISORY.replace(
+            "date = \"2021-11-16\"",
+            "date = \"2021-11-16\"\nwithdrawn = \"2021-12-01\"",
+        );
+        assert!(Advisory::parse(&withdrawn).unwrap().is_none());
+    }
+
+    #[test]
+    fn reports_all_versions_without_ranges() {
+        let advisory = Advisory::parse(
+            "```toml\n[advisory]\nid = \"RUSTSEC-2020-0036\"\npackage = \"failure\"\n\
+             informational = \"unmaintained\"\n\n[versions]\npatched = []\n```\n\n\
+             # failure is officially deprecated/unmaintained\n",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(advisory.informational.as_deref(), Some("unmaintained"));
+        assert_eq!(advisory.affected_versions(), "all versions");
+        assert!(advisory.affects(&version("0.1.8")));
+    }
+
+    #[test]
+    fn rejects_unterminated_front_matter() {
+        assert!(Advisory::parse("```toml\n[advisory]\nid = \"x\"\n").is_err());
+    }
+}
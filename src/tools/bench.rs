@@ -0,0 +1,291 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// One benchmark measurement, in nanoseconds per iteration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchResult {
+    pub name: String,
+    /// `libtest` or `criterion`.
+    pub source: String,
+    pub ns_per_iter: f64,
+    /// `+/-` of libtest, standard deviation of Criterion.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deviation_ns: Option<f64>,
+}
+
+/// Parse `test fib ... bench:       1,234.50 ns/iter (+/- 56.10)` lines.
+pub fn parse_libtest_benches(output: &str) -> Vec<BenchResult> {
+    let number = |text: &str| text.replace(',', "").parse::<f64>().ok();
+
+    output
+        .lines()
+        .filter_map(|line| {
+            let rest = line.trim().strip_prefix("test ")?;
+            let (name, measurement) = rest.split_once(" ... bench:")?;
+            let (time, rest) = measurement.trim().split_once(" ns/iter")?;
+            let deviation = rest
+                .trim()
+                .strip_prefix("(+/-")
+                .and_then(|d| d.trim().strip_suffix(')'))
+                .and_then(|d| number(d.trim()));
+            Some(BenchResult {
+                name: name.trim().to_string(),
+                source: "libtest".to_string(),
+                ns_per_iter: number(time.trim())?,
+                deviation_ns: deviation,
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+struct CriterionEstimate {
+    point_estimate: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct CriterionEstimates {
+    mean: CriterionEstimate,
+    #[serde(default)]
+    std_dev: Option<CriterionEstimate>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CriterionBenchmark {
+    full_id: String,
+}
+
+/// Read the `new/estimates.json` files Criterion wrote under
+/// `target/criterion` since `since`, so results of benchmarks that did not
+/// run this time are left out.
+pub fn read_criterion_estimates(criterion_dir: &Path, since: SystemTime) -> Vec<BenchResult> {
+    let mut results = Vec::new();
+    let mut pending = vec![criterion_dir.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            // `report` directories only hold the HTML report.
+            if path.is_dir() && path.file_name().is_some_and(|name| name != "report") {
+                pending.push(path);
+            }
+        }
+
+        let estimates_path = dir.join("new").join("estimates.json");
+        let fresh = std::fs::metadata(&estimates_path)
+            .and_then(|metadata| metadata.modified())
+            .is_ok_and(|modified| modified >= since);
+        if !fresh {
+            continue;
+        }
+        let Some(estimates) = std::fs::read_to_string(&estimates_path)
+            .ok()
+            .and_then(|text| serde_json::from_str::<CriterionEstimates>(&text).ok())
+        else {
+            continue;
+        };
+        let name = std::fs::read_to_string(dir.join("new").join("benchmark.json"))
+            .ok()
+            .and_then(|text| serde_json::from_str::<CriterionBenchmark>(&text).ok())
+            .map(|benchmark| benchmark.full_id)
+            .unwrap_or_else(|| {
+                dir.strip_prefix(criterion_dir)
+                    .unwrap_or(&dir)
+                    .display()
+                    .to_string()
+            });
+
+        results.push(BenchResult {
+            name,
+            source: "criterion".to_string(),
+            ns_per_iter: estimates.mean.point_estimate,
+            deviation_ns: estimates.std_dev.map(|d| d.point_estimate),
+        });
+    }
+
+    results.sort_by(|a, b| a.name.cmp(&b.name));
+    results
+}
+
+/// Saved measurements, keyed by benchmark name.
+pub type Baseline = BTreeMap<String, f64>;
+
+/// Baseline names become file names, so keep them to a safe alphabet.
+pub fn is_valid_baseline_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        && !name.starts_with('.')
+}
+
+pub fn load_baseline(dir: &Path, name: &str) -> Option<Baseline> {
+    let text = std::fs::read_to_string(dir.join(format!("{name}.json"))).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+pub fn save_baseline(dir: &Path, name: &str, results: &[BenchResult]) -> std::io::Result<()> {
+    let baseline: Baseline = results
+        .iter()
+        .map(|result| (result.name.clone(), result.ns_per_iter))
+        .collect();
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(
+        dir.join(format!("{name}.json")),
+        serde_json::to_string_pretty(&baseline)?,
+    )
+}
+
+/// A benchmark compared with its baseline measurement.
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchComparison {
+    #[serde(flatten)]
+    pub result: BenchResult,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub baseline_ns: Option<f64>,
+    /// Positive when the benchmark got slower.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub change_percent: Option<f64>,
+    pub regression: bool,
+}
+
+pub fn compare(
+    results: Vec<BenchResult>,
+    baseline: Option<&Baseline>,
+    threshold_percent: f64,
+) -> Vec<BenchComparison> {
+    results
+        .into_iter()
+        .map(|result| {
+            let baseline_ns = baseline
+                .and_then(|baseline| baseline.get(&result.name))
+                .copied()
+                .filter(|ns| *ns > 0.0);
+            let change_percent = baseline_ns.map(|old| {
+                let change = (result.ns_per_iter - old) / old * 100.0;
+                (change * 100.0).round() / 100.0
+            });
+            BenchComparison {
+                regression: change_percent.is_some_and(|change| change > threshold_percent),
+                result,
+                baseline_ns,
+                change_percent,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `cargo +nightly bench` on three `#[bench]` functions.
+    const LIBTEST_OUTPUT: &str = include_str!("testdata/bench_libtest.stdout");
+
+    fn result(name: &str, ns_per_iter: f64) -> BenchResult {
+        BenchResult {
+            name: name.to_string(),
+            source: "libtest".to_string(),
+            ns_per_iter,
+            deviation_ns: None,
+        }
+    }
+
+    #[test]
+    fn parses_libtest_bench_lines() {
+        let results = parse_libtest_benches(LIBTEST_OUTPUT);
+        let parsed: Vec<(&str, f64, Option<f64>)> = results
+            .iter()
+            .map(|r| (r.name.as_str(), r.ns_per_iter, r.deviation_ns))
+            .collect();
+        assert_eq!(
+            parsed,
+            [
+                ("benches::fib_10", 239.53, Some(41.72)),
+                ("benches::fib_20", 29307.29, Some(7309.98)),
+                ("benches::noop", 0.4, Some(0.37)),
+            ]
+        );
+        assert!(results.iter().all(|r| r.source == "libtest"));
+    }
+
+    #[test]
+    fn ignores_non_bench_lines() {
+        let output = "test tests::it_works ... ok\ntest benches::slow ... bench: n/a\n";
+        assert!(parse_libtest_benches(output).is_empty());
+    }
+
+    #[test]
+    fn compares_with_baseline() {
+        let baseline = Baseline::from([
+            ("fast".to_string(), 100.0),
+            ("slow".to_string(), 100.0),
+            ("zero".to_string(), 0.0),
+        ]);
+        let results = vec![
+            result("fast", 90.0),
+            result("slow", 112.5),
+            result("new", 50.0),
+            result("zero", 1.0),
+        ];
+
+        let comparisons = compare(results, Some(&baseline), 10.0);
+        let summary: Vec<(&str, Option<f64>, Option<f64>, bool)> = comparisons
+            .iter()
+            .map(|c| {
+                (
+                    c.result.name.as_str(),
+                    c.baseline_ns,
+                    c.change_percent,
+                    c.regression,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("fast", Some(100.0), Some(-10.0), false),
+                ("slow", Some(100.0), Some(12.5), true),
+                ("new", None, None, false),
+                ("zero", None, None, false),
+            ]
+        );
+
+        // Within the threshold is not a regression.
+        let comparisons = compare(vec![result("slow", 112.5)], Some(&baseline), 15.0);
+        assert!(!comparisons[0].regression);
+        assert!(
+            compare(vec![result("slow", 1e9)], None, 0.0)[0]
+                .change_percent
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn saves_and_loads_baselines() {
+        let dir = std::env::temp_dir().join(format!("cargo-mcp-bench-{}", std::process::id()));
+        let results = parse_libtest_benches(LIBTEST_OUTPUT);
+        save_baseline(&dir, "main", &results).unwrap();
+
+        let baseline = load_baseline(&dir, "main").unwrap();
+        assert_eq!(baseline.len(), 3);
+        assert_eq!(baseline["benches::fib_20"], 29307.29);
+        assert!(load_baseline(&dir, "other").is_none());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn validates_baseline_names() {
+        assert!(is_valid_baseline_name("main"));
+        assert!(is_valid_baseline_name("v1.2_pre-release"));
+        assert!(!is_valid_baseline_name(""));
+        assert!(!is_valid_baseline_name(".hidden"));
+        assert!(!is_valid_baseline_name("../main"));
+        assert!(!is_valid_baseline_name("with space"));
+    }
+}
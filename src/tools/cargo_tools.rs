@@ -0,0 +1,579 @@
+use serde_json::{Map, Value, json};
+
+use super::schemas::{bench_properties, diagnostics_properties, output_schema};
+use crate::types::{Tool, ToolAnnotations};
+
+/// Merge several `properties` objects into one input schema.
+pub(super) fn object_schema(groups: &[Value], required: &[&str]) -> Value {
+    let mut properties = Map::new();
+    for group in groups {
+        if let Some(group) = group.as_object() {
+            properties.extend(group.clone());
+        }
+    }
+
+    let mut schema = json!({
+        "type": "object",
+        "properties": properties
+    });
+    if !required.is_empty() {
+        schema["required"] = json!(required);
+    }
+    schema
+}
+
+pub(super) fn working_directory_property() -> Value {
+    json!({
+        "working_directory": {
+            "type": "string",
+            "description": "Working directory to run cargo command in"
+        }
+    })
+}
+
+pub(super) fn common_properties() -> Value {
+    let mut properties = working_directory_property();
+    properties["timeout_secs"] = json!({
+            "type": "integer",
+        "description": "Kill cargo if it runs longer than this many seconds (defaults to the server setting, 0 disables)"
+    });
+    properties
+}
+
+fn package_property() -> Value {
+    json!({
+        "package": {
+            "type": "string",
+            "description": "Package to operate on (equivalent to -p flag)"
+        }
+    })
+}
+
+fn feature_properties() -> Value {
+    json!({
+        "features": {
+            "type": "array",
+            "items": {"type": "string"},
+            "description": "Features to activate"
+        },
+        "all_features": {
+            "type": "boolean",
+            "description": "Activate all available features"
+        },
+        "no_default_features": {
+            "type": "boolean",
+            "description": "Do not activate the default feature"
+        }
+    })
+}
+
+fn target_selection_properties() -> Value {
+    json!({
+        "lib": {
+            "type": "boolean",
+            "description": "Only this package's library"
+        },
+        "bin": {
+            "type": "string",
+            "description": "Only the specified binary"
+        },
+        "bins": {
+            "type": "boolean",
+            "description": "All binaries"
+        },
+        "example": {
+            "type": "string",
+            "description": "Only the specified example"
+        },
+        "examples": {
+            "type": "boolean",
+            "description": "All examples"
+        },
+        "test": {
+            "type": "string",
+            "description": "Only the specified test target"
+        },
+        "tests": {
+            "type": "boolean",
+            "description": "All tests"
+        },
+        "bench": {
+            "type": "string",
+            "description": "Only the specified bench target"
+        },
+        "benches": {
+            "type": "boolean",
+            "description": "All benches"
+        },
+        "all_targets": {
+            "type": "boolean",
+            "description": "All targets"
+        }
+    })
+}
+
+fn compilation_properties() -> Value {
+    json!({
+        "release": {
+            "type": "boolean",
+            "description": "Build artifacts in release mode, with optimizations"
+        },
+        "profile": {
+            "type": "string",
+            "description": "Build artifacts with the specified profile"
+        },
+        "target": {
+            "type": "string",
+            "description": "Build for the target triple"
+        },
+        "jobs": {
+            "type": "integer",
+            "description": "Number of parallel jobs"
+        },
+        "workspace": {
+            "type": "boolean",
+            "description": "Operate on all packages in the workspace"
+        },
+        "exclude": {
+            "type": "array",
+            "items": {"type": "string"},
+            "description": "Exclude packages from the operation (requires workspace)"
+        }
+    })
+}
+
+fn template_properties() -> Value {
+    json!({
+        "bin_template": {
+            "type": "boolean",
+            "description": "Use a binary (application) template"
+        },
+        "lib_template": {
+            "type": "boolean",
+            "description": "Use a library template"
+        },
+        "name": {
+            "type": "string",
+            "description": "Set the resulting package name (defaults to the directory name)"
+        },
+        "edition": {
+            "type": "string",
+            "description": "Edition to set for the generated crate (2015, 2018, 2021, 2024)"
+        },
+        "registry": {
+            "type": "string",
+            "description": "Registry to use"
+        }
+    })
+}
+
+/// Tools that map one-to-one onto a cargo subcommand: `cargo_command`
+/// builds the command line and `handle_cargo_subcommand` runs it.
+pub fn get_cargo_tools() -> Vec<Tool> {
+    vec![
+        Tool {
+            name: "build".to_string(),
+            description: "Compile the package and its dependencies, producing binaries".to_string(),
+            input_schema: object_schema(
+                &[
+                    common_properties(),
+                    package_property(),
+                    feature_properties(),
+                    target_selection_properties(),
+                    compilation_properties(),
+                    json!({
+                        "message_format": {
+                            "type": "string",
+                            "description": "Error format (human, short, json; default: short)"
+                        }
+                    }),
+                ],
+                &[],
+            ),
+            output_schema: output_schema(diagnostics_properties()),
+            annotations: ToolAnnotations::mutating(false, true, false),
+        },
+        Tool {
+            name: "run".to_string(),
+            description: "Build and run a binary or example of the local package".to_string(),
+            input_schema: object_schema(
+                &[
+                    common_properties(),
+                    package_property(),
+                    feature_properties(),
+                    json!({
+                        "bin": {
+                            "type": "string",
+                            "description": "Name of the binary to run"
+                        },
+                        "example": {
+                            "type": "string",
+                            "description": "Name of the example to run"
+                        },
+                        "release": {
+                            "type": "boolean",
+                            "description": "Build and run in release mode"
+                        },
+                        "target": {
+                            "type": "string",
+                            "description": "Build for the target triple"
+                        },
+                        "args": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "description": "Arguments passed to the program"
+                        }
+                    }),
+                ],
+                &[],
+            ),
+            output_schema: output_schema(json!({})),
+            annotations: ToolAnnotations::mutating(true, false, true),
+        },
+        Tool {
+            name: "fmt".to_string(),
+            description: "Format the package's sources with rustfmt".to_string(),
+            input_schema: object_schema(
+                &[
+                    common_properties(),
+                    package_property(),
+                    json!({
+                        "check": {
+                            "type": "boolean",
+                            "description": "Only report formatting differences instead of rewriting files"
+                        }
+                    }),
+                ],
+                &[],
+            ),
+            output_schema: output_schema(json!({})),
+            annotations: ToolAnnotations::mutating(false, true, false),
+        },
+        Tool {
+            name: "doc".to_string(),
+            description: "Build documentation for the package and its dependencies".to_string(),
+            input_schema: object_schema(
+                &[
+                    common_properties(),
+                    package_property(),
+                    feature_properties(),
+                    compilation_properties(),
+                    json!({
+                        "no_deps": {
+                            "type": "boolean",
+                            "description": "Don't build documentation for dependencies"
+                        },
+                        "document_private_items": {
+                            "type": "boolean",
+                            "description": "Document private items"
+                        }
+                    }),
+                ],
+                &[],
+            ),
+            output_schema: output_schema(json!({})),
+            annotations: ToolAnnotations::mutating(false, true, false),
+        },
+        Tool {
+            name: "bench".to_string(),
+            description: "Compile and run benchmarks, parse libtest and Criterion results, and compare them with a saved baseline".to_string(),
+            input_schema: object_schema(
+                &[
+                    common_properties(),
+                    package_property(),
+                    feature_properties(),
+                    target_selection_properties(),
+                    compilation_properties(),
+                    json!({
+                        "test_name": {
+                            "type": "string",
+                            "description": "Only run benchmarks whose name contains this string"
+                        },
+                        "save_baseline": {
+                            "type": "string",
+                            "description": "Save the results under this baseline name"
+                        },
+                        "baseline": {
+                            "type": "string",
+                            "description": "Compare the results with this saved baseline"
+                        },
+                        "regression_threshold": {
+                            "type": "number",
+                            "description": "Percentage slowdown against the baseline reported as a regression (default: 5)"
+                        }
+                    }),
+                ],
+                &[],
+            ),
+            output_schema: output_schema(bench_properties()),
+            annotations: ToolAnnotations::mutating(false, true, false),
+        },
+        Tool {
+            name: "update".to_string(),
+            description: "Update dependencies as recorded in Cargo.lock".to_string(),
+            input_schema: object_schema(
+                &[
+                    common_properties(),
+                    json!({
+                        "package": {
+                            "type": "string",
+                            "description": "Only update this dependency (and what it requires)"
+                        },
+                        "aggressive": {
+                            "type": "boolean",
+                            "description": "Also update all dependencies of the selected package"
+                        },
+                        "precise": {
+                            "type": "string",
+                            "description": "Update the selected package to exactly this version"
+                        },
+                        "workspace": {
+                            "type": "boolean",
+                            "description": "Only update the workspace packages"
+                        },
+                        "dry_run": {
+                            "type": "boolean",
+                            "description": "Report what would change without writing Cargo.lock"
+                        }
+                    }),
+                ],
+                &[],
+            ),
+            output_schema: output_schema(json!({})),
+            annotations: ToolAnnotations::mutating(false, false, true),
+        },
+        Tool {
+            name: "tree".to_string(),
+            description: "Display a tree visualization of the dependency graph".to_string(),
+            input_schema: object_schema(
+                &[
+                    common_properties(),
+                    package_property(),
+                    feature_properties(),
+                    json!({
+                        "target": {
+                            "type": "string",
+                            "description": "Filter dependencies matching the target triple (or \"all\")"
+                        },
+                        "edges": {
+                            "type": "string",
+                            "description": "Dependency kinds to display (normal, build, dev, all, no-dev, no-build, no-normal, features)"
+                        },
+                        "invert": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "description": "Invert the tree and show what depends on these packages"
+                        },
+                        "prune": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "description": "Prune these packages from the display"
+                        },
+                        "depth": {
+                            "type": "integer",
+                            "description": "Maximum display depth of the tree"
+                        },
+                        "prefix": {
+                            "type": "string",
+                            "description": "How to display each line (indent, depth, none)"
+                        },
+                        "duplicates": {
+                            "type": "boolean",
+                            "description": "Show only dependencies which come in multiple versions"
+                        },
+                        "charset": {
+                            "type": "string",
+                            "description": "Character set to use in output (utf8, ascii)"
+                        },
+                        "format": {
+                            "type": "string",
+                            "description": "Format string used for printing dependencies, e.g. \"{p} {f}\""
+                        }
+                    }),
+                ],
+                &[],
+            ),
+            output_schema: output_schema(json!({})),
+            annotations: ToolAnnotations::read_only(false),
+        },
+        Tool {
+            name: "new".to_string(),
+            description: "Create a new cargo package at the given path".to_string(),
+            input_schema: object_schema(
+                &[
+                    common_properties(),
+                    template_properties(),
+                    json!({
+                        "path": {
+                            "type": "string",
+                            "description": "Path of the new package, relative to working_directory"
+                        }
+                    }),
+                ],
+                &["path"],
+            ),
+            output_schema: output_schema(json!({})),
+            annotations: ToolAnnotations::mutating(false, false, false),
+        },
+        Tool {
+            name: "init".to_string(),
+            description: "Create a new cargo package in an existing directory".to_string(),
+            input_schema: object_schema(
+                &[
+                    common_properties(),
+                    template_properties(),
+                    json!({
+                        "path": {
+                            "type": "string",
+                            "description": "Directory to initialize (defaults to working_directory)"
+                        }
+                    }),
+                ],
+                &[],
+            ),
+            output_schema: output_schema(json!({})),
+            annotations: ToolAnnotations::mutating(false, false, false),
+        },
+        Tool {
+            name: "metadata".to_string(),
+            description: "Output the resolved dependencies and workspace layout as JSON"
+                .to_string(),
+            input_schema: object_schema(
+                &[
+                    common_properties(),
+                    feature_properties(),
+                    json!({
+                        "no_deps": {
+                            "type": "boolean",
+                            "description": "Only output workspace members, without resolving dependencies"
+                        },
+                        "format_version": {
+                            "type": "integer",
+                            "description": "Format version of the output (currently 1)"
+                        }
+                    }),
+                ],
+                &[],
+            ),
+            output_schema: output_schema(json!({})),
+            annotations: ToolAnnotations::read_only(false),
+        },
+        Tool {
+            name: "install".to_string(),
+            description: "Install a Rust binary, or list installed binaries".to_string(),
+            input_schema: object_schema(
+                &[
+                    common_properties(),
+                    feature_properties(),
+                    json!({
+                        "query": {
+                            "type": "string",
+                            "description": "Name of the crate to install"
+                        },
+                        "version": {
+                            "type": "string",
+                            "description": "Version requirement to install"
+                        },
+                        "git_url": {
+                            "type": "string",
+                            "description": "Git URL to install the crate from"
+                        },
+                        "branch_install": {
+                            "type": "string",
+                            "description": "Git branch to use with git_url"
+                        },
+                        "tag_install": {
+                            "type": "string",
+                            "description": "Git tag to use with git_url"
+                        },
+                        "rev_install": {
+                            "type": "string",
+                            "description": "Git commit to use with git_url"
+                        },
+                        "path_install": {
+                            "type": "string",
+                            "description": "Filesystem path to a local crate to install"
+                        },
+                        "bin_install": {
+                            "type": "string",
+                            "description": "Install only the specified binary"
+                        },
+                        "bins_install": {
+                            "type": "boolean",
+                            "description": "Install all binaries"
+                        },
+                        "example_install": {
+                            "type": "string",
+                            "description": "Install only the specified example"
+                        },
+                        "examples_install": {
+                            "type": "boolean",
+                            "description": "Install all examples"
+                        },
+                        "force": {
+                            "type": "boolean",
+                            "description": "Force overwriting existing crates or binaries"
+                        },
+                        "no_track": {
+                            "type": "boolean",
+                            "description": "Do not save tracking information"
+                        },
+                        "locked": {
+                            "type": "boolean",
+                            "description": "Use the crate's Cargo.lock as-is"
+                        },
+                        "root": {
+                            "type": "string",
+                            "description": "Directory to install packages into"
+                        },
+                        "registry": {
+                            "type": "string",
+                            "description": "Registry to use"
+                        },
+                        "index": {
+                            "type": "string",
+                            "description": "Registry index to install from"
+                        },
+                        "target": {
+                            "type": "string",
+                            "description": "Build for the target triple"
+                        },
+                        "list": {
+                            "type": "boolean",
+                            "description": "List all installed packages and their versions instead of installing"
+                        }
+                    }),
+                ],
+                &[],
+            ),
+            output_schema: output_schema(json!({})),
+            annotations: ToolAnnotations::mutating(true, false, true),
+        },
+        Tool {
+            name: "uninstall".to_string(),
+            description: "Remove a Rust binary installed with cargo install".to_string(),
+            input_schema: object_schema(
+                &[
+                    common_properties(),
+                    json!({
+                        "query": {
+                            "type": "string",
+                            "description": "Name of the package to uninstall"
+                        },
+                        "bin_install": {
+                            "type": "string",
+                            "description": "Only uninstall the specified binary"
+                        },
+                        "root": {
+                            "type": "string",
+                            "description": "Directory to uninstall packages from"
+                        }
+                    }),
+                ],
+                &["query"],
+            ),
+            output_schema: output_schema(json!({})),
+            annotations: ToolAnnotations::mutating(true, true, false),
+        },
+    ]
+}
@@ -0,0 +1,71 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+
+use super::progress::ProgressReporter;
+use crate::config::{ServerConfig, TestRunner};
+use crate::types::CargoToolParams;
+
+/// Shared flag used to abort an in-flight tool call.
+#[derive(Debug, Clone)]
+pub struct CancellationToken(Arc<watch::Sender<bool>>);
+
+impl Default for CancellationToken {
+    fn default() -> Self {
+        Self(Arc::new(watch::Sender::new(false)))
+    }
+}
+
+impl CancellationToken {
+    pub fn cancel(&self) {
+        self.0.send_replace(true);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Resolves once `cancel` has been called.
+    pub async fn cancelled(&self) {
+        let mut receiver = self.0.subscribe();
+        let _ = receiver.wait_for(|cancelled| *cancelled).await;
+    }
+}
+
+/// Per-call settings handed to every tool handler.
+#[derive(Debug, Clone)]
+pub struct ToolContext {
+    /// Maximum wall-clock time for a single cargo invocation.
+    pub timeout: Option<Duration>,
+    /// Set when the client cancels the request.
+    pub cancellation: CancellationToken,
+    /// Present when the client asked for progress notifications.
+    pub progress: Option<Arc<ProgressReporter>>,
+    /// Runner requested for the test tool.
+    pub test_runner: TestRunner,
+    /// Advisory database checkout for the audit tool, when one was given.
+    pub advisory_db: Option<PathBuf>,
+}
+
+impl ToolContext {
+    pub fn new(
+        config: &ServerConfig,
+        params: &CargoToolParams,
+        cancellation: CancellationToken,
+        progress: Option<ProgressReporter>,
+    ) -> Self {
+        let timeout_secs = params.timeout_secs.unwrap_or(config.timeout_secs);
+        Self {
+            timeout: (timeout_secs > 0).then(|| Duration::from_secs(timeout_secs)),
+            cancellation,
+            progress: progress.map(Arc::new),
+            test_runner: params.runner.unwrap_or(config.test_runner),
+            advisory_db: params
+                .advisory_db
+                .as_ref()
+                .map(PathBuf::from)
+                .or_else(|| config.advisory_db.clone()),
+        }
+    }
+}
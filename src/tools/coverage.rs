@@ -0,0 +1,253 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+use super::registry_index::cargo_home;
+
+/// `llvm-profdata` and `llvm-cov` from the active toolchain's `llvm-tools`
+/// component.
+#[derive(Debug)]
+pub struct LlvmTools {
+    pub profdata: PathBuf,
+    pub cov: PathBuf,
+}
+
+impl LlvmTools {
+    /// Look the tools up in `<sysroot>/lib/rustlib/<host>/bin`, where rustup
+    /// installs them.
+    pub fn locate(sysroot: &str, host: &str) -> Option<Self> {
+        let bin = Path::new(sysroot.trim())
+            .join("lib/rustlib")
+            .join(host.trim())
+            .join("bin");
+        let tool = |name: &str| {
+            let path = bin.join(format!("{name}{}", std::env::consts::EXE_SUFFIX));
+            path.is_file().then_some(path)
+        };
+        Some(Self {
+            profdata: tool("llvm-profdata")?,
+            cov: tool("llvm-cov")?,
+        })
+    }
+}
+
+/// Add `-C instrument-coverage` to the rustflags of a cargo command without
+/// losing the flags a normal build would use. Cargo takes rustflags from
+/// the first of `CARGO_ENCODED_RUSTFLAGS`, `RUSTFLAGS`, `target.*.rustflags`
+/// and `build.rustflags` that is set, so the flag is added to that source.
+/// Arrays passed with `--config` are appended to those from config files.
+pub fn add_instrument_coverage(cmd: &mut Command, working_dir: &Path, triple: &str) {
+    if let Some(flags) = std::env::var_os("CARGO_ENCODED_RUSTFLAGS") {
+        let flags = flags.to_string_lossy();
+        let mut encoded: Vec<&str> = flags
+            .split('\x1f')
+            .filter(|flag| !flag.is_empty())
+            .collect();
+        encoded.extend(["-C", "instrument-coverage"]);
+        cmd.env("CARGO_ENCODED_RUSTFLAGS", encoded.join("\x1f"));
+    } else if let Ok(flags) = std::env::var("RUSTFLAGS") {
+        cmd.env("RUSTFLAGS", format!("{flags} -C instrument-coverage"));
+    } else {
+        let key = if config_sets_target_rustflags(working_dir) {
+            format!("target.{triple:?}.rustflags")
+        } else {
+            "build.rustflags".to_string()
+        };
+        cmd.arg("--config")
+            .arg(format!(r#"{key}=["-C", "instrument-coverage"]"#));
+    }
+}
+
+/// Whether a cargo config file that applies in `working_dir` sets
+/// `target.<triple>.rustflags` or `target.<cfg>.rustflags`, which take
+/// precedence over `build.rustflags`.
+fn config_sets_target_rustflags(working_dir: &Path) -> bool {
+    let working_dir =
+        std::path::absolute(working_dir).unwrap_or_else(|_| working_dir.to_path_buf());
+    working_dir
+        .ancestors()
+        .map(|dir| dir.join(".cargo"))
+        .chain(cargo_home())
+        .flat_map(|dir| [dir.join("config.toml"), dir.join("config")])
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .filter_map(|contents| contents.parse::<toml::Table>().ok())
+        .any(|config| {
+            config
+                .get("target")
+                .and_then(toml::Value::as_table)
+                .is_some_and(|targets| {
+                    targets
+                        .values()
+                        .any(|target| target.get("rustflags").is_some())
+                })
+        })
+}
+
+/// Line and function coverage of one source file.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FileCoverage {
+    pub file: String,
+    pub lines_covered: u64,
+    pub lines_total: u64,
+    pub functions_covered: u64,
+    pub functions_total: u64,
+    /// Execution count per instrumented line.
+    #[serde(skip)]
+    pub line_hits: Vec<(u32, u64)>,
+}
+
+fn percent(covered: u64, total: u64) -> f64 {
+    if total == 0 {
+        100.0
+    } else {
+        // Two decimals are plenty for a report.
+        (covered as f64 * 10000.0 / total as f64).round() / 100.0
+    }
+}
+
+impl FileCoverage {
+    pub fn line_percent(&self) -> f64 {
+        percent(self.lines_covered, self.lines_total)
+    }
+
+    pub fn function_percent(&self) -> f64 {
+        percent(self.functions_covered, self.functions_total)
+    }
+
+    /// Runs of instrumented lines that never executed, as inclusive
+    /// `(start, end)` pairs. Lines without code do not break a run.
+    pub fn uncovered_ranges(&self) -> Vec<(u32, u32)> {
+        let mut hits = self.line_hits.clone();
+        hits.sort_unstable();
+
+        let mut ranges: Vec<(u32, u32)> = Vec::new();
+        let mut in_run = false;
+        for (line, count) in hits {
+            if count > 0 {
+                in_run = false;
+            } else if in_run && let Some(last) = ranges.last_mut() {
+                last.1 = line;
+            } else {
+                ranges.push((line, line));
+                in_run = true;
+            }
+        }
+        ranges
+    }
+}
+
+/// Parse an lcov tracefile as written by `llvm-cov export -format=lcov`.
+pub fn parse_lcov(lcov: &str) -> Vec<FileCoverage> {
+    let mut files = Vec::new();
+    let mut current: Option<FileCoverage> = None;
+
+    for line in lcov.lines() {
+        let line = line.trim();
+        if let Some(file) = line.strip_prefix("SF:") {
+            current = Some(FileCoverage {
+                file: file.to_string(),
+                ..FileCoverage::default()
+            });
+        } else if line == "end_of_record" {
+            files.extend(current.take());
+        } else if let Some(file) = current.as_mut()
+            && let Some((key, value)) = line.split_once(':')
+        {
+            let number = |value: &str| value.parse::<u64>().unwrap_or_default();
+            match key {
+                "DA" => {
+                    let mut fields = value.split(',');
+                    if let (Some(line), Some(count)) = (fields.next(), fields.next())
+                        && let Ok(line) = line.parse()
+                    {
+                        file.line_hits.push((line, number(count)));
+                    }
+                }
+                "LF" => file.lines_total = number(value),
+                "LH" => file.lines_covered = number(value),
+                "FNF" => file.functions_total = number(value),
+                "FNH" => file.functions_covered = number(value),
+                _ => {}
+            }
+        }
+    }
+
+    files
+}
+
+/// Executables of the test targets cargo built, from the `compiler-artifact`
+/// records of its JSON message stream.
+pub fn test_executables(stdout: &str) -> Vec<String> {
+    let mut executables = Vec::new();
+    for line in stdout.lines() {
+        if !line.starts_with('{') {
+            continue;
+        }
+        let Ok(message) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        if message["reason"] == "compiler-artifact"
+            && message["profile"]["test"] == true
+            && let Some(executable) = message["executable"].as_str()
+            && !executables.iter().any(|e| e == executable)
+        {
+            executables.push(executable.to_string());
+        }
+    }
+    executables
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `llvm-cov export -format=lcov` for a crate whose `unused` function and
+    /// two `classify` branches are never reached by its one test.
+    const LCOV: &str = include_str!("testdata/coverage.lcov");
+
+    /// `cargo test --message-format=json` stdout: cargo's JSON records
+    /// followed by the test binary's own plain output.
+    const BUILD_OUTPUT: &str = include_str!("testdata/coverage_build.stdout");
+
+    #[test]
+    fn parses_lcov_totals() {
+        let files = parse_lcov(LCOV);
+        assert_eq!(files.len(), 1);
+
+        let file = &files[0];
+        assert_eq!(file.file, "/tmp/cv/src/lib.rs");
+        assert_eq!((file.lines_covered, file.lines_total), (9, 16));
+        assert_eq!((file.functions_covered, file.functions_total), (2, 3));
+        assert_eq!(file.line_percent(), 56.25);
+        assert_eq!(file.function_percent(), 66.67);
+        assert_eq!(file.line_hits.len(), 16);
+        assert_eq!(file.line_hits[2], (3, 0));
+    }
+
+    #[test]
+    fn reports_uncovered_line_ranges() {
+        let files = parse_lcov(LCOV);
+        assert_eq!(files[0].uncovered_ranges(), [(3, 3), (5, 5), (11, 15)]);
+
+        // Lines without code between two missed lines keep the run going.
+        let file = FileCoverage {
+            line_hits: vec![(9, 0), (4, 0), (2, 1), (6, 0)],
+            ..FileCoverage::default()
+        };
+        assert_eq!(file.uncovered_ranges(), [(4, 9)]);
+    }
+
+    #[test]
+    fn ignores_records_without_source_file() {
+        assert!(parse_lcov("DA:1,1\nLF:1\nLH:1\nend_of_record\n").is_empty());
+        assert_eq!(FileCoverage::default().line_percent(), 100.0);
+    }
+
+    #[test]
+    fn finds_test_executables() {
+        assert_eq!(
+            test_executables(BUILD_OUTPUT),
+            ["/tmp/cv/target/debug/deps/cv-d45e47943bc1d942"]
+        );
+    }
+}
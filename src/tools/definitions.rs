@@ -1,6 +1,9 @@
+use super::cargo_tools::get_cargo_tools;
 use super::workflow_tools::get_workflow_tools;
 use crate::types::Tool;
 
 pub fn get_available_tools() -> Vec<Tool> {
-    get_workflow_tools()
+    let mut tools = get_workflow_tools();
+    tools.extend(get_cargo_tools());
+    tools
 }
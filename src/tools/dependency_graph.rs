@@ -0,0 +1,704 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+
+use super::metadata::{DepKindInfo, Metadata, NodeDep, Package};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphNode {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    pub workspace_member: bool,
+    /// Features enabled on this package.
+    pub features: Vec<String>,
+    #[serde(skip)]
+    proc_macro: bool,
+}
+
+impl GraphNode {
+    pub fn label(&self) -> String {
+        format!("{}@{}", self.name, self.version)
+    }
+
+    fn matches_spec(&self, spec: &str) -> bool {
+        match spec.split_once('@') {
+            Some((name, version)) => self.name == name && self.version == version,
+            None => self.name == spec,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphEdge {
+    pub from: String,
+    pub to: String,
+    /// Name the dependent uses for the dependency.
+    pub name: String,
+    pub kinds: Vec<DepKindInfo>,
+    /// Version requirements the dependent declares.
+    pub req: Vec<String>,
+    /// Features of the dependent that turned this optional dependency on.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub activated_by: Vec<String>,
+    /// Features this dependent requests on the dependency.
+    pub features: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyGraph {
+    pub roots: Vec<String>,
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// Dependency kinds to follow, parsed from cargo tree's `--edges` syntax.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EdgeKinds {
+    pub normal: bool,
+    pub build: bool,
+    pub dev: bool,
+    pub proc_macro: bool,
+}
+
+impl Default for EdgeKinds {
+    fn default() -> Self {
+        Self {
+            normal: true,
+            build: true,
+            dev: true,
+            proc_macro: true,
+        }
+    }
+}
+
+impl EdgeKinds {
+    /// Accepts a comma-separated list of `all`, `normal`, `build`, `dev`,
+    /// their `no-` forms, `no-proc-macro` and `features`. Naming any kind
+    /// selects only the named kinds. Feature labels are always present on
+    /// edges, so `features` changes nothing.
+    pub fn parse(edges: &str) -> Result<Self, String> {
+        let mut kinds = Self::default();
+        let mut named = None;
+        for edge in edges.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            match edge {
+                "all" | "features" => {}
+                "normal" | "build" | "dev" => {
+                    let named = named.get_or_insert(Self {
+                        normal: false,
+                        build: false,
+                        dev: false,
+                        proc_macro: true,
+                    });
+                    match edge {
+                        "normal" => named.normal = true,
+                        "build" => named.build = true,
+                        _ => named.dev = true,
+                    }
+                }
+                "no-normal" => kinds.normal = false,
+                "no-build" => kinds.build = false,
+                "no-dev" => kinds.dev = false,
+                "no-proc-macro" => kinds.proc_macro = false,
+                other => return Err(format!("Unknown edge kind {other:?}")),
+            }
+        }
+        if let Some(named) = named {
+            kinds.normal &= named.normal;
+            kinds.build &= named.build;
+            kinds.dev &= named.dev;
+        }
+        Ok(kinds)
+    }
+
+    fn allows(&self, kind: &DepKindInfo) -> bool {
+        match kind.kind() {
+            "dev" => self.dev,
+            "build" => self.build,
+            _ => self.normal,
+        }
+    }
+}
+
+/// Which part of the graph to keep, mirroring `cargo tree`'s options.
+#[derive(Debug, Default)]
+pub struct GraphFilter {
+    pub kinds: EdgeKinds,
+    /// Show what depends on these packages instead of what they depend on.
+    pub invert: Vec<String>,
+    /// Packages whose dependencies are left out.
+    pub prune: Vec<String>,
+    pub depth: Option<u32>,
+    /// Keep only packages resolved to several versions, and what depends
+    /// on them. Implies inverting on those packages.
+    pub duplicates: bool,
+}
+
+impl DependencyGraph {
+    /// Build the whole resolved graph. Edges carry the dependency kinds,
+    /// the declared requirements, and the features involved.
+    pub fn from_metadata(metadata: &Metadata) -> Result<Self> {
+        let resolve = metadata
+            .resolve
+            .as_ref()
+            .context("cargo metadata did not include a resolved dependency graph")?;
+        let packages: HashMap<&str, &Package> = metadata
+            .packages
+            .iter()
+            .map(|package| (package.id.as_str(), package))
+            .collect();
+
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        for node in &resolve.nodes {
+            let Some(package) = packages.get(node.id.as_str()) else {
+                continue;
+            };
+            nodes.push(GraphNode {
+                id: node.id.clone(),
+                name: package.name.clone(),
+                version: package.version.clone(),
+                source: package.source.clone(),
+                workspace_member: metadata.workspace_members.contains(&node.id),
+                features: node.features.clone(),
+                proc_macro: package.is_proc_macro(),
+            });
+
+            for dep in &node.deps {
+                let Some(dependency) = packages.get(dep.pkg.as_str()) else {
+                    continue;
+                };
+                edges.push(describe_edge(package, &node.features, dependency, dep));
+            }
+        }
+        nodes.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+
+        let mut roots: Vec<String> = metadata.workspace_members.clone();
+        roots.sort();
+        Ok(Self {
+            roots,
+            nodes,
+            edges,
+        })
+    }
+
+    fn node(&self, id: &str) -> Option<&GraphNode> {
+        self.nodes.iter().find(|node| node.id == id)
+    }
+
+    pub fn label(&self, id: &str) -> String {
+        self.node(id)
+            .map(GraphNode::label)
+            .unwrap_or_else(|| id.to_string())
+    }
+
+    /// Ids of the nodes `spec` (`name` or `name@version`) refers to.
+    pub fn find(&self, spec: &str) -> Vec<String> {
+        self.nodes
+            .iter()
+            .filter(|node| node.matches_spec(spec))
+            .map(|node| node.id.clone())
+            .collect()
+    }
+
+    /// The graph with only the edge kinds `kinds` allows, dropping edges
+    /// left without any kind.
+    fn with_edge_kinds(&self, kinds: EdgeKinds) -> Self {
+        let edges = self
+            .edges
+            .iter()
+            .filter(|edge| kinds.proc_macro || !self.node(&edge.to).is_some_and(|n| n.proc_macro))
+            .filter_map(|edge| {
+                let mut edge = edge.clone();
+                edge.kinds.retain(|kind| kinds.allows(kind));
+                (!edge.kinds.is_empty()).then_some(edge)
+            })
+            .collect();
+        Self {
+            roots: self.roots.clone(),
+            nodes: self.nodes.clone(),
+            edges,
+        }
+    }
+
+    /// Names resolved to more than one version, with the ids of each
+    /// version.
+    pub fn duplicates(&self) -> BTreeMap<String, Vec<String>> {
+        let mut versions: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for node in &self.nodes {
+            versions
+                .entry(node.name.clone())
+                .or_default()
+                .push(node.id.clone());
+        }
+        versions.retain(|_, ids| ids.len() > 1);
+        versions
+    }
+
+    /// The part of the graph reachable from the roots under `filter`.
+    pub fn filter(&self, filter: &GraphFilter) -> Self {
+        let graph = self
+            .with_edge_kinds(filter.kinds)
+            .walk(&self.roots, false, &[], None);
+        let (roots, inverted) = if filter.duplicates {
+            (graph.duplicates().into_values().flatten().collect(), true)
+        } else if !filter.invert.is_empty() {
+            let roots = filter
+                .invert
+                .iter()
+                .flat_map(|spec| graph.find(spec))
+                .collect();
+            (roots, true)
+        } else {
+            (self.roots.clone(), false)
+        };
+
+        graph.walk(&roots, inverted, &filter.prune, filter.depth)
+    }
+
+    /// Ids of every package that depends on `id`, directly or not.
+    pub fn ancestors(&self, id: &str) -> BTreeSet<String> {
+        self.walk(&[id.to_string()], true, &[], None)
+            .nodes
+            .into_iter()
+            .map(|node| node.id)
+            .filter(|ancestor| ancestor != id)
+            .collect()
+    }
+
+    /// Ids of every package `id` depends on, directly or not.
+    pub fn descendants(&self, id: &str) -> BTreeSet<String> {
+        self.walk(&[id.to_string()], false, &[], None)
+            .nodes
+            .into_iter()
+            .map(|node| node.id)
+            .filter(|descendant| descendant != id)
+            .collect()
+    }
+
+    pub fn edges_to<'a>(&'a self, id: &'a str) -> impl Iterator<Item = &'a GraphEdge> {
+        self.edges.iter().filter(move |edge| edge.to == id)
+    }
+
+    /// Breadth-first walk from `roots`, following edges backwards when
+    /// `inverted`. Pruned packages are kept but not expanded.
+    fn walk(&self, roots: &[String], inverted: bool, prune: &[String], depth: Option<u32>) -> Self {
+        let mut adjacency: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (index, edge) in self.edges.iter().enumerate() {
+            let from = if inverted { &edge.to } else { &edge.from };
+            adjacency.entry(from.as_str()).or_default().push(index);
+        }
+
+        let mut seen: BTreeSet<String> = roots.iter().cloned().collect();
+        let mut kept_edges = BTreeSet::new();
+        let mut queue: VecDeque<(String, u32)> = roots.iter().map(|id| (id.clone(), 0)).collect();
+        while let Some((id, level)) = queue.pop_front() {
+            let pruned = self
+                .node(&id)
+                .is_some_and(|node| prune.iter().any(|spec| node.matches_spec(spec)));
+            if pruned || depth.is_some_and(|depth| level >= depth) {
+                continue;
+            }
+            for &index in adjacency.get(id.as_str()).into_iter().flatten() {
+                kept_edges.insert(index);
+                let edge = &self.edges[index];
+                let next = if inverted { &edge.from } else { &edge.to };
+                if seen.insert(next.clone()) {
+                    queue.push_back((next.clone(), level + 1));
+                }
+            }
+        }
+
+        Self {
+            roots: roots.to_vec(),
+            nodes: self
+                .nodes
+                .iter()
+                .filter(|node| seen.contains(&node.id))
+                .cloned()
+                .collect(),
+            edges: kept_edges
+                .into_iter()
+                .map(|index| self.edges[index].clone())
+                .collect(),
+        }
+    }
+
+    /// Every path from the roots to a package matching `spec`, as node ids,
+    /// following only edges of the given kinds. Stops after `limit` paths;
+    /// the flag reports whether more exist.
+    pub fn paths_to(&self, spec: &str, kinds: EdgeKinds, limit: usize) -> (Vec<Vec<String>>, bool) {
+        let graph = self.with_edge_kinds(kinds);
+        let targets = graph.find(spec);
+        // Only nodes that lead to a target are worth exploring.
+        let leads_to_target: BTreeSet<String> = graph
+            .walk(&targets, true, &[], None)
+            .nodes
+            .into_iter()
+            .map(|node| node.id)
+            .collect();
+
+        let mut adjacency: HashMap<&str, Vec<&str>> = HashMap::new();
+        for edge in &graph.edges {
+            if leads_to_target.contains(&edge.to) {
+                adjacency
+                    .entry(edge.from.as_str())
+                    .or_default()
+                    .push(edge.to.as_str());
+            }
+        }
+        for next in adjacency.values_mut() {
+            next.sort_unstable();
+            next.dedup();
+        }
+
+        let mut paths = Vec::new();
+        let mut truncated = false;
+        for root in &self.roots {
+            if !leads_to_target.contains(root) {
+                continue;
+            }
+            let mut path = vec![root.as_str()];
+            collect_paths(
+                &adjacency,
+                &targets,
+                &mut path,
+                &mut paths,
+                limit,
+                &mut truncated,
+            );
+        }
+        (paths, truncated)
+    }
+}
+
+impl DependencyGraph {
+    /// The nodes and edges that make up `paths`, as returned by
+    /// `paths_to` with the same `kinds`.
+    pub fn along(&self, paths: &[Vec<String>], kinds: EdgeKinds) -> Self {
+        let on_paths: BTreeSet<&String> = paths.iter().flatten().collect();
+        let steps: BTreeSet<(&String, &String)> = paths
+            .iter()
+            .flat_map(|path| path.iter().zip(path.iter().skip(1)))
+            .collect();
+        Self {
+            roots: self.roots.clone(),
+            nodes: self
+                .nodes
+                .iter()
+                .filter(|node| on_paths.contains(&node.id))
+                .cloned()
+                .collect(),
+            edges: self
+                .with_edge_kinds(kinds)
+                .edges
+                .into_iter()
+                .filter(|edge| steps.contains(&(&edge.from, &edge.to)))
+                .collect(),
+        }
+    }
+}
+
+fn collect_paths<'a>(
+    adjacency: &HashMap<&'a str, Vec<&'a str>>,
+    targets: &[String],
+    path: &mut Vec<&'a str>,
+    paths: &mut Vec<Vec<String>>,
+    limit: usize,
+    truncated: &mut bool,
+) {
+    let last = path[path.len() - 1];
+    if targets.iter().any(|target| target == last) {
+        if paths.len() == limit {
+            *truncated = true;
+        } else {
+            paths.push(path.iter().map(|id| id.to_string()).collect());
+        }
+        return;
+    }
+    for &next in adjacency.get(last).into_iter().flatten() {
+        if *truncated {
+            return;
+        }
+        // Dev-dependency cycles can lead back into the path.
+        if path.contains(&next) {
+            continue;
+        }
+        path.push(next);
+        collect_paths(adjacency, targets, path, paths, limit, truncated);
+        path.pop();
+    }
+}
+
+/// Label an edge with the declared requirements and the features that
+/// enabled it or that it enables.
+fn describe_edge(
+    package: &Package,
+    active_features: &[String],
+    dependency: &Package,
+    dep: &NodeDep,
+) -> GraphEdge {
+    let declared: Vec<_> = package
+        .dependencies
+        .iter()
+        .filter(|declared| declared.name == dependency.name)
+        .filter(|declared| {
+            declared
+                .rename
+                .as_ref()
+                .is_none_or(|rename| rename.replace('-', "_") == dep.name)
+        })
+        .filter(|declared| {
+            dep.dep_kinds
+                .iter()
+                .any(|kind| kind.kind() == declared.kind() && kind.target == declared.target)
+        })
+        .collect();
+
+    let mut req = Vec::new();
+    let mut features = BTreeSet::new();
+    let mut activated_by = BTreeSet::new();
+    for declared in &declared {
+        if !req.contains(&declared.req) {
+            req.push(declared.req.clone());
+        }
+        features.extend(declared.features.iter().cloned());
+        if declared.uses_default_features {
+            features.insert("default".to_string());
+        }
+
+        let local_name = declared.rename.as_deref().unwrap_or(&declared.name);
+        for feature in active_features {
+            for enabled in package.features.get(feature).into_iter().flatten() {
+                let (target, dependency_feature, weak) = match enabled.split_once('/') {
+                    Some((target, feature)) => match target.strip_suffix('?') {
+                        Some(target) => (target, Some(feature), true),
+                        None => (target, Some(feature), false),
+                    },
+                    None => (enabled.strip_prefix("dep:").unwrap_or(enabled), None, false),
+                };
+                if target != local_name {
+                    continue;
+                }
+                if let Some(dependency_feature) = dependency_feature {
+                    features.insert(dependency_feature.to_string());
+                }
+                if declared.optional && !weak {
+                    activated_by.insert(feature.clone());
+                }
+            }
+        }
+    }
+
+    GraphEdge {
+        from: package.id.clone(),
+        to: dependency.id.clone(),
+        name: dep.name.clone(),
+        kinds: dep.dep_kinds.clone(),
+        req,
+        activated_by: activated_by.into_iter().collect(),
+        features: features.into_iter().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph() -> DependencyGraph {
+        let metadata: Metadata =
+            serde_json::from_str(include_str!("testdata/metadata_workspace.json")).unwrap();
+        DependencyGraph::from_metadata(&metadata).unwrap()
+    }
+
+    fn labels<'a>(
+        graph: &DependencyGraph,
+        ids: impl IntoIterator<Item = &'a String>,
+    ) -> Vec<String> {
+        ids.into_iter().map(|id| graph.label(id)).collect()
+    }
+
+    fn edge<'a>(graph: &'a DependencyGraph, from: &str, to: &str) -> &'a GraphEdge {
+        let (from, to) = (&graph.find(from)[0], &graph.find(to)[0]);
+        graph
+            .edges
+            .iter()
+            .find(|edge| &edge.from == from && &edge.to == to)
+            .unwrap()
+    }
+
+    #[test]
+    fn parses_edge_kinds() {
+        let kinds = |normal, build, dev, proc_macro| EdgeKinds {
+            normal,
+            build,
+            dev,
+            proc_macro,
+        };
+        assert_eq!(EdgeKinds::parse(""), Ok(EdgeKinds::default()));
+        assert_eq!(EdgeKinds::parse("all, features"), Ok(EdgeKinds::default()));
+        assert_eq!(
+            EdgeKinds::parse("normal"),
+            Ok(kinds(true, false, false, true))
+        );
+        assert_eq!(
+            EdgeKinds::parse("build,dev"),
+            Ok(kinds(false, true, true, true))
+        );
+        assert_eq!(
+            EdgeKinds::parse("no-dev,no-proc-macro"),
+            Ok(kinds(true, true, false, false))
+        );
+        assert_eq!(
+            EdgeKinds::parse("normal,build,no-build"),
+            Ok(kinds(true, false, false, true))
+        );
+        assert_eq!(
+            EdgeKinds::parse("normal,optional"),
+            Err("Unknown edge kind \"optional\"".to_string())
+        );
+    }
+
+    #[test]
+    fn describes_edges() {
+        let graph = graph();
+        assert_eq!(
+            labels(&graph, &graph.roots),
+            ["app@0.1.0", "core-utils@0.2.0", "macros@0.1.0"]
+        );
+        assert_eq!(
+            graph.duplicates().keys().collect::<Vec<_>>(),
+            ["cfg-if", "itoa"]
+        );
+
+        let renamed = edge(&graph, "app", "itoa@0.4.8");
+        assert_eq!(renamed.name, "old_itoa");
+        assert_eq!(renamed.req, ["^0.4"]);
+        assert_eq!(renamed.features, ["default"]);
+
+        let core_utils = edge(&graph, "app", "core-utils");
+        assert_eq!(core_utils.features, ["default", "fast"]);
+        assert!(core_utils.activated_by.is_empty());
+
+        let memchr = edge(&graph, "core-utils", "memchr");
+        assert_eq!(memchr.activated_by, ["fast"]);
+
+        let libc = edge(&graph, "core-utils", "libc");
+        assert_eq!(libc.kinds[0].target.as_deref(), Some("cfg(unix)"));
+        assert!(libc.features.is_empty());
+
+        assert_eq!(edge(&graph, "app", "either").kinds[0].kind(), "dev");
+        assert_eq!(edge(&graph, "app", "autocfg").kinds[0].kind(), "build");
+    }
+
+    #[test]
+    fn finds_every_path_to_a_package() {
+        let graph = graph();
+        let paths = |spec, kinds, limit| {
+            let (paths, truncated) = graph.paths_to(spec, kinds, limit);
+            let paths: Vec<Vec<String>> = paths.iter().map(|path| labels(&graph, path)).collect();
+            (paths, truncated)
+        };
+
+        assert_eq!(
+            paths("itoa", EdgeKinds::default(), 10),
+            (
+                vec![
+                    vec!["app@0.1.0", "core-utils@0.2.0", "itoa@1.0.18"],
+                    vec!["app@0.1.0", "itoa@0.4.8"],
+                    vec!["core-utils@0.2.0", "itoa@1.0.18"],
+                ]
+                .into_iter()
+                .map(|path| path.into_iter().map(String::from).collect())
+                .collect(),
+                false
+            )
+        );
+        let (limited, truncated) = paths("itoa@1.0.18", EdgeKinds::default(), 1);
+        assert_eq!(limited, [["app@0.1.0", "core-utils@0.2.0", "itoa@1.0.18"]]);
+        assert!(truncated);
+
+        assert_eq!(
+            paths("either", EdgeKinds::parse("no-dev").unwrap(), 10)
+                .0
+                .len(),
+            0
+        );
+        assert_eq!(
+            paths("macros", EdgeKinds::parse("no-proc-macro").unwrap(), 10).0,
+            [["macros@0.1.0"]]
+        );
+        assert!(paths("missing", EdgeKinds::default(), 10).0.is_empty());
+
+        let (found, _) = graph.paths_to("memchr", EdgeKinds::default(), 10);
+        let along = graph.along(&found, EdgeKinds::default());
+        assert_eq!(
+            labels(&graph, along.nodes.iter().map(|node| &node.id)),
+            ["app@0.1.0", "core-utils@0.2.0", "memchr@2.8.3"]
+        );
+        assert_eq!(along.edges.len(), 2);
+    }
+
+    #[test]
+    fn filters_like_cargo_tree() {
+        let graph = graph();
+        let nodes = |filter: GraphFilter| -> Vec<String> {
+            let filtered = graph.filter(&filter);
+            labels(&graph, filtered.nodes.iter().map(|node| &node.id))
+        };
+
+        assert_eq!(
+            nodes(GraphFilter {
+                kinds: EdgeKinds::parse("normal,no-proc-macro").unwrap(),
+                prune: vec!["core-utils".to_string()],
+                ..GraphFilter::default()
+            }),
+            [
+                "app@0.1.0",
+                "cfg-if@0.1.10",
+                "core-utils@0.2.0",
+                "itoa@0.4.8",
+                "macros@0.1.0"
+            ]
+        );
+        assert_eq!(
+            nodes(GraphFilter {
+                invert: vec!["itoa@1.0.18".to_string()],
+                depth: Some(1),
+                ..GraphFilter::default()
+            }),
+            ["core-utils@0.2.0", "itoa@1.0.18"]
+        );
+        assert_eq!(
+            nodes(GraphFilter {
+                invert: vec!["itoa@1.0.18".to_string()],
+                ..GraphFilter::default()
+            }),
+            ["app@0.1.0", "core-utils@0.2.0", "itoa@1.0.18"]
+        );
+        assert_eq!(
+            nodes(GraphFilter {
+                duplicates: true,
+                kinds: EdgeKinds::parse("no-normal").unwrap(),
+                ..GraphFilter::default()
+            }),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            nodes(GraphFilter {
+                duplicates: true,
+                ..GraphFilter::default()
+            }),
+            [
+                "app@0.1.0",
+                "cfg-if@0.1.10",
+                "cfg-if@1.0.5",
+                "core-utils@0.2.0",
+                "itoa@0.4.8",
+                "itoa@1.0.18"
+            ]
+        );
+    }
+}
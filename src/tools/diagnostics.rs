@@ -0,0 +1,384 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::output::command_result;
+use super::process::CommandOutput;
+
+/// A single line of `--message-format=json` output emitted by cargo.
+#[derive(Debug, Deserialize)]
+struct CargoMessage {
+    reason: String,
+    #[serde(default)]
+    message: Option<RustcDiagnostic>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RustcDiagnostic {
+    message: String,
+    #[serde(default)]
+    code: Option<RustcCode>,
+    level: String,
+    #[serde(default)]
+    spans: Vec<RustcSpan>,
+    #[serde(default)]
+    children: Vec<RustcDiagnostic>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RustcCode {
+    code: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RustcSpan {
+    file_name: String,
+    byte_start: u32,
+    byte_end: u32,
+    line_start: u32,
+    line_end: u32,
+    column_start: u32,
+    column_end: u32,
+    is_primary: bool,
+    #[serde(default)]
+    label: Option<String>,
+    #[serde(default)]
+    suggested_replacement: Option<String>,
+    #[serde(default)]
+    suggestion_applicability: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SpanLocation {
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticLabel {
+    #[serde(flatten)]
+    pub location: SpanLocation,
+    pub label: String,
+    pub primary: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticChild {
+    pub level: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub span: Option<SpanLocation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Suggestion {
+    #[serde(flatten)]
+    pub location: SpanLocation,
+    pub byte_start: u32,
+    pub byte_end: u32,
+    pub replacement: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub applicability: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostic {
+    /// Handle for `apply_fixes`, assigned when compile or lint remembers
+    /// the diagnostic.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub level: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub span: Option<SpanLocation>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<DiagnosticLabel>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<DiagnosticChild>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub suggestions: Vec<Suggestion>,
+}
+
+impl Diagnostic {
+    /// One-line rendering used in the human-readable summary.
+    pub fn headline(&self) -> String {
+        let mut line = match &self.code {
+            Some(code) => format!("{}[{}]: {}", self.level, code, self.message),
+            None => format!("{}: {}", self.level, self.message),
+        };
+        if let Some(id) = &self.id {
+            line = format!("[{id}] {line}");
+        }
+        if let Some(span) = &self.span {
+            line.push_str(&format!(" ({}:{}:{})", span.file, span.line, span.column));
+        }
+        line
+    }
+}
+
+impl From<&RustcSpan> for SpanLocation {
+    fn from(span: &RustcSpan) -> Self {
+        Self {
+            file: span.file_name.clone(),
+            line: span.line_start,
+            column: span.column_start,
+            end_line: span.line_end,
+            end_column: span.column_end,
+        }
+    }
+}
+
+fn collect_suggestions(diagnostic: &RustcDiagnostic, suggestions: &mut Vec<Suggestion>) {
+    for span in &diagnostic.spans {
+        if let Some(replacement) = &span.suggested_replacement {
+            suggestions.push(Suggestion {
+                location: span.into(),
+                byte_start: span.byte_start,
+                byte_end: span.byte_end,
+                replacement: replacement.clone(),
+                applicability: span.suggestion_applicability.clone(),
+            });
+        }
+    }
+    for child in &diagnostic.children {
+        collect_suggestions(child, suggestions);
+    }
+}
+
+fn convert(diagnostic: &RustcDiagnostic) -> Diagnostic {
+    let span = diagnostic
+        .spans
+        .iter()
+        .find(|span| span.is_primary)
+        .map(SpanLocation::from);
+
+    let labels = diagnostic
+        .spans
+        .iter()
+        .filter_map(|span| {
+            span.label.as_ref().map(|label| DiagnosticLabel {
+                location: span.into(),
+                label: label.clone(),
+                primary: span.is_primary,
+            })
+        })
+        .collect();
+
+    let children = diagnostic
+        .children
+        .iter()
+        .map(|child| DiagnosticChild {
+            level: child.level.clone(),
+            message: child.message.clone(),
+            span: child
+                .spans
+                .iter()
+                .find(|span| span.is_primary)
+                .map(SpanLocation::from),
+        })
+        .collect();
+
+    let mut suggestions = Vec::new();
+    collect_suggestions(diagnostic, &mut suggestions);
+
+    Diagnostic {
+        id: None,
+        level: diagnostic.level.clone(),
+        code: diagnostic.code.as_ref().map(|code| code.code.clone()),
+        message: diagnostic.message.clone(),
+        span,
+        labels,
+        children,
+        suggestions,
+    }
+}
+
+/// Rustc trailer messages ("aborting due to ...", "For more information ...")
+/// carry no information beyond the diagnostics that precede them.
+fn is_trailer(diagnostic: &RustcDiagnostic) -> bool {
+    diagnostic.level == "failure-note"
+        || (diagnostic.spans.is_empty()
+            && (diagnostic.message.starts_with("aborting due to")
+                || diagnostic.message.ends_with("warning emitted")
+                || diagnostic.message.ends_with("warnings emitted")))
+}
+
+/// Parse the `compiler-message` records out of cargo's JSON message stream.
+///
+/// Lines that are not JSON (for instance test or build script output) are
+/// ignored, and diagnostics reported once per target (e.g. lib and test
+/// builds of the same file) are only returned once.
+pub fn parse_diagnostics(stdout: &str) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+
+    for line in stdout.lines() {
+        let Ok(message) = serde_json::from_str::<CargoMessage>(line) else {
+            continue;
+        };
+        if message.reason != "compiler-message" {
+            continue;
+        }
+        let Some(rustc) = message.message else {
+            continue;
+        };
+        if is_trailer(&rustc) {
+            continue;
+        }
+
+        let diagnostic = convert(&rustc);
+        let duplicate = diagnostics.iter().any(|existing| {
+            existing.level == diagnostic.level
+                && existing.code == diagnostic.code
+                && existing.message == diagnostic.message
+                && existing.span == diagnostic.span
+        });
+        if !duplicate {
+            diagnostics.push(diagnostic);
+        }
+    }
+
+    diagnostics
+}
+
+/// Count diagnostics per level, e.g. `2 errors, 1 warning`.
+pub fn summarize(diagnostics: &[Diagnostic]) -> String {
+    let count = |level: &str| diagnostics.iter().filter(|d| d.level == level).count();
+    let errors = count("error");
+    let warnings = count("warning");
+
+    let plural = |n: usize, word: &str| {
+        if n == 1 {
+            format!("{n} {word}")
+        } else {
+            format!("{n} {word}s")
+        }
+    };
+
+    format!(
+        "{}, {}",
+        plural(errors, "error"),
+        plural(warnings, "warning")
+    )
+}
+
+/// Build the tool result shared by the build-style tools: a short human
+/// summary plus the diagnostics in `structuredContent`. A failed build is
+/// flagged with `isError`.
+pub fn diagnostics_result(
+    title: &str,
+    output: &CommandOutput,
+    diagnostics: &[Diagnostic],
+) -> Value {
+    let success = output.success();
+    let status = if success { "succeeded" } else { "failed" };
+    let mut summary = format!("{title} {status}: {}", summarize(diagnostics));
+
+    for diagnostic in diagnostics {
+        summary.push_str("\n- ");
+        summary.push_str(&diagnostic.headline());
+    }
+
+    // Failures that happen before rustc runs (bad manifest, build script
+    // panics, ...) only show up on stderr.
+    if !success
+        && diagnostics.iter().all(|d| d.level != "error")
+        && !output.stderr.trim().is_empty()
+    {
+        summary.push_str("\n\n");
+        summary.push_str(output.stderr.trim_end());
+    }
+
+    let count = |level: &str| diagnostics.iter().filter(|d| d.level == level).count();
+    command_result(
+        summary,
+        output,
+        serde_json::json!({
+            "diagnostics": diagnostics,
+            "error_count": count("error"),
+            "warning_count": count("warning"),
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `cargo check --lib --tests --keep-going --message-format=json` on a
+    /// crate with a borrow error and an unused variable. Both targets report
+    /// the same two diagnostics.
+    const CHECK_OUTPUT: &str = include_str!("testdata/check_borrow_error.json");
+
+    #[test]
+    fn parses_compiler_messages_once_per_span() {
+        let output = format!("   Compiling dg v0.1.0 (/tmp/dg)\n{CHECK_OUTPUT}");
+        let diagnostics = parse_diagnostics(&output);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(summarize(&diagnostics), "1 error, 1 warning");
+
+        let error = &diagnostics[0];
+        assert_eq!(error.level, "error");
+        assert_eq!(error.code.as_deref(), Some("E0502"));
+        assert_eq!(
+            error.span,
+            Some(SpanLocation {
+                file: "src/lib.rs".to_string(),
+                line: 4,
+                column: 5,
+                end_line: 4,
+                end_column: 18,
+            })
+        );
+        let labels: Vec<(&str, u32, bool)> = error
+            .labels
+            .iter()
+            .map(|label| (label.label.as_str(), label.location.line, label.primary))
+            .collect();
+        assert_eq!(
+            labels,
+            [
+                ("mutable borrow occurs here", 4, true),
+                ("immutable borrow occurs here", 3, false),
+                ("immutable borrow later used here", 5, false),
+            ]
+        );
+        assert!(error.suggestions.is_empty());
+        assert_eq!(
+            error.headline(),
+            "error[E0502]: cannot borrow `*v` as mutable because it is also borrowed as immutable (src/lib.rs:4:5)"
+        );
+    }
+
+    #[test]
+    fn collects_suggestions_from_children() {
+        let diagnostics = parse_diagnostics(CHECK_OUTPUT);
+        let warning = &diagnostics[1];
+        assert_eq!(warning.code.as_deref(), Some("unused_variables"));
+        assert_eq!(warning.children.len(), 2);
+        assert_eq!(warning.children[1].level, "help");
+
+        let [suggestion] = warning.suggestions.as_slice() else {
+            panic!("expected one suggestion, got {:?}", warning.suggestions);
+        };
+        assert_eq!(suggestion.replacement, "_unused");
+        assert_eq!((suggestion.byte_start, suggestion.byte_end), (48, 54));
+        assert_eq!(
+            suggestion.applicability.as_deref(),
+            Some("MachineApplicable")
+        );
+    }
+
+    #[test]
+    fn skips_trailers_and_other_records() {
+        let trailers: Vec<&str> = CHECK_OUTPUT
+            .lines()
+            .filter(|line| line.contains("failure-note") || line.contains("build-finished"))
+            .collect();
+        assert_eq!(trailers.len(), 3);
+        assert!(parse_diagnostics(&trailers.join("\n")).is_empty());
+    }
+}
@@ -0,0 +1,356 @@
+use semver::{Version, VersionReq};
+use serde::Serialize;
+
+use super::dependency_graph::DependencyGraph;
+
+#[derive(Debug, Serialize)]
+pub struct Dependent {
+    #[serde(skip)]
+    id: String,
+    /// `name@version` of the package requiring this version.
+    pub package: String,
+    pub req: Vec<String>,
+    /// Whether `req` also accepts the version the others resolved to.
+    pub accepts_target: bool,
+}
+
+/// How one version could be merged into the newest resolved version.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case", tag = "action")]
+pub enum ConsolidationHint {
+    /// Every requirement already accepts the newer version, so the lock
+    /// file only needs updating.
+    UpdatePrecise { command: String },
+    /// A single package holds the old version back: a workspace member's
+    /// own requirement, or one of its direct dependencies.
+    BumpDependency { package: String, message: String },
+    /// Several packages require the old version.
+    None { message: String },
+}
+
+#[derive(Debug, Serialize)]
+pub struct DuplicateVersion {
+    pub version: String,
+    pub dependents: Vec<Dependent>,
+    /// Direct dependencies of the workspace members that lead to this
+    /// version, or the member itself when it depends on it directly.
+    pub pulled_in_by: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<ConsolidationHint>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DuplicateCrate {
+    pub name: String,
+    /// Oldest first; the last one is the version the others could unify on.
+    pub versions: Vec<DuplicateVersion>,
+}
+
+/// Every package resolved to more than one version, with what pulls each
+/// version in and how it could be unified with the newest one.
+pub fn find_duplicates(graph: &DependencyGraph) -> Vec<DuplicateCrate> {
+    let members: Vec<&String> = graph
+        .nodes
+        .iter()
+        .filter(|node| node.workspace_member)
+        .map(|node| &node.id)
+        .collect();
+    let mut direct: Vec<&String> = graph
+        .edges
+        .iter()
+        .filter(|edge| members.contains(&&edge.from))
+        .map(|edge| &edge.to)
+        .collect();
+    direct.sort();
+    direct.dedup();
+
+    let mut duplicates = Vec::new();
+    for (name, ids) in graph.duplicates() {
+        let mut versions: Vec<(Version, String)> = ids
+            .into_iter()
+            .filter_map(|id| {
+                let node = graph.nodes.iter().find(|node| node.id == id)?;
+                Some((Version::parse(&node.version).ok()?, id))
+            })
+            .collect();
+        versions.sort();
+        let Some((newest, _)) = versions.last().cloned() else {
+            continue;
+        };
+
+        let versions = versions
+            .iter()
+            .map(|(version, id)| {
+                let dependents: Vec<Dependent> = graph
+                    .edges_to(id)
+                    .map(|edge| Dependent {
+                        id: edge.from.clone(),
+                        package: graph.label(&edge.from),
+                        req: edge.req.clone(),
+                        accepts_target: edge.req.iter().all(|req| {
+                            VersionReq::parse(req).is_ok_and(|req| req.matches(&newest))
+                        }),
+                    })
+                    .collect();
+
+                let ancestors = graph.ancestors(id);
+                let mut pulled_in_by: Vec<String> = direct
+                    .iter()
+                    .copied()
+                    .filter(|dependency| *dependency == id || ancestors.contains(*dependency))
+                    .flat_map(|dependency| {
+                        if dependency == id {
+                            // A member requires this version itself.
+                            graph
+                                .edges_to(id)
+                                .filter(|edge| members.contains(&&edge.from))
+                                .map(|edge| graph.label(&edge.from))
+                                .collect()
+                        } else {
+                            vec![graph.label(dependency)]
+                        }
+                    })
+                    .collect();
+                pulled_in_by.sort();
+                pulled_in_by.dedup();
+
+                let hint = (*version != newest).then(|| {
+                    consolidation_hint(
+                        &name,
+                        version,
+                        &newest,
+                        &dependents,
+                        &members,
+                        &direct,
+                        graph,
+                    )
+                });
+
+                DuplicateVersion {
+                    version: version.to_string(),
+                    dependents,
+                    pulled_in_by,
+                    hint,
+                }
+            })
+            .collect();
+
+        duplicates.push(DuplicateCrate { name, versions });
+    }
+    duplicates
+}
+
+fn consolidation_hint(
+    name: &str,
+    version: &Version,
+    newest: &Version,
+    dependents: &[Dependent],
+    members: &[&String],
+    direct: &[&String],
+    graph: &DependencyGraph,
+) -> ConsolidationHint {
+    let blockers: Vec<&Dependent> = dependents
+        .iter()
+        .filter(|dependent| !dependent.accepts_target)
+        .collect();
+    let [blocker] = blockers.as_slice() else {
+        if blockers.is_empty() {
+            return ConsolidationHint::UpdatePrecise {
+                command: format!("cargo update -p {name}@{version} --precise {newest}"),
+            };
+        }
+        return ConsolidationHint::None {
+            message: format!(
+                "{} packages require {name} {version} and not {newest}: {}",
+                blockers.len(),
+                blockers
+                    .iter()
+                    .map(|blocker| blocker.package.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        };
+    };
+
+    let requirement = format!(
+        "{} requires {name} {}",
+        blocker.package,
+        blocker.req.join(", ")
+    );
+    let blocker_id = &blocker.id;
+    if members.contains(&blocker_id) {
+        return ConsolidationHint::BumpDependency {
+            package: blocker.package.clone(),
+            message: format!("{requirement}; raise that requirement to accept {newest}"),
+        };
+    }
+    if direct.contains(&blocker_id) {
+        return ConsolidationHint::BumpDependency {
+            package: blocker.package.clone(),
+            message: format!("{requirement}; bump it to a release that depends on {name} {newest}"),
+        };
+    }
+
+    let ancestors = graph.ancestors(blocker_id);
+    let mut via: Vec<&String> = direct
+        .iter()
+        .copied()
+        .filter(|dependency| ancestors.contains(*dependency))
+        .collect();
+    via.sort();
+    via.dedup();
+    match via.as_slice() {
+        [dependency] => ConsolidationHint::BumpDependency {
+            package: graph.label(dependency),
+            message: format!(
+                "{requirement} and comes in through {via}; bumping {via} may pick up releases that depend on {name} {newest}",
+                via = graph.label(dependency)
+            ),
+        },
+        _ => ConsolidationHint::None {
+            message: format!("{requirement}, which several direct dependencies pull in"),
+        },
+    }
+}
+
+impl DuplicateCrate {
+    pub fn summary(&self) -> String {
+        let mut text = format!(
+            "{}: {}",
+            self.name,
+            self.versions
+                .iter()
+                .map(|version| version.version.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        for version in &self.versions {
+            text.push_str(&format!(
+                "\n  {} via {}",
+                version.version,
+                version.pulled_in_by.join(", ")
+            ));
+            match &version.hint {
+                Some(ConsolidationHint::UpdatePrecise { command }) => {
+                    text.push_str(&format!("\n    run: {command}"));
+                }
+                Some(ConsolidationHint::BumpDependency { message, .. })
+                | Some(ConsolidationHint::None { message }) => {
+                    text.push_str(&format!("\n    {message}"));
+                }
+                None => {}
+            }
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::metadata::Metadata;
+
+    /// The workspace's graph: `app` requires itoa ^0.4 and cfg-if ^0.1 while
+    /// its member `core-utils` requires ^1 of both.
+    fn graph() -> DependencyGraph {
+        let metadata: Metadata =
+            serde_json::from_str(include_str!("testdata/metadata_workspace.json")).unwrap();
+        DependencyGraph::from_metadata(&metadata).unwrap()
+    }
+
+    /// Make `from` require `to` with `req`, adding the edge if needed.
+    fn require(graph: &mut DependencyGraph, from: &str, to: &str, req: &str) {
+        let (from, to) = (graph.find(from).remove(0), graph.find(to).remove(0));
+        let index = match graph
+            .edges
+            .iter()
+            .position(|edge| edge.from == from && edge.to == to)
+        {
+            Some(index) => index,
+            None => {
+                let mut edge = graph.edges[0].clone();
+                edge.from = from;
+                edge.to = to;
+                graph.edges.push(edge);
+                graph.edges.len() - 1
+            }
+        };
+        graph.edges[index].req = vec![req.to_string()];
+    }
+
+    fn itoa_versions(graph: &DependencyGraph) -> DuplicateCrate {
+        find_duplicates(graph)
+            .into_iter()
+            .find(|duplicate| duplicate.name == "itoa")
+            .unwrap()
+    }
+
+    #[test]
+    fn asks_members_to_raise_their_own_requirements() {
+        let duplicates = find_duplicates(&graph());
+        assert_eq!(
+            duplicates
+                .iter()
+                .map(|duplicate| duplicate.name.as_str())
+                .collect::<Vec<_>>(),
+            ["cfg-if", "itoa"]
+        );
+        assert_eq!(
+            duplicates[1].summary(),
+            "itoa: 0.4.8, 1.0.18
+  0.4.8 via app@0.1.0
+    app@0.1.0 requires itoa ^0.4; raise that requirement to accept 1.0.18
+  1.0.18 via core-utils@0.2.0"
+        );
+
+        let old = &duplicates[1].versions[0];
+        assert_eq!(old.dependents.len(), 1);
+        assert_eq!(old.dependents[0].package, "app@0.1.0");
+        assert!(!old.dependents[0].accepts_target);
+        assert!(matches!(
+            &old.hint,
+            Some(ConsolidationHint::BumpDependency { package, .. }) if package == "app@0.1.0"
+        ));
+        assert!(duplicates[1].versions[1].hint.is_none());
+    }
+
+    #[test]
+    fn suggests_a_precise_update_when_every_requirement_accepts_the_newest() {
+        let mut graph = graph();
+        require(&mut graph, "app", "itoa@0.4.8", ">=0.4");
+        let itoa = itoa_versions(&graph);
+        assert!(itoa.versions[0].dependents[0].accepts_target);
+        assert!(matches!(
+            &itoa.versions[0].hint,
+            Some(ConsolidationHint::UpdatePrecise { command })
+                if command == "cargo update -p itoa@0.4.8 --precise 1.0.18"
+        ));
+    }
+
+    #[test]
+    fn points_at_the_direct_dependency_holding_a_version_back() {
+        let mut graph = graph();
+        require(&mut graph, "app", "itoa@0.4.8", ">=0.4");
+        require(&mut graph, "memchr", "itoa@0.4.8", "^0.4");
+        let itoa = itoa_versions(&graph);
+        assert_eq!(
+            itoa.versions[0].pulled_in_by,
+            ["app@0.1.0", "core-utils@0.2.0", "memchr@2.8.3"]
+        );
+        assert!(matches!(
+            &itoa.versions[0].hint,
+            Some(ConsolidationHint::BumpDependency { package, message })
+                if package == "memchr@2.8.3"
+                    && message == "memchr@2.8.3 requires itoa ^0.4; bump it to a release that depends on itoa 1.0.18"
+        ));
+
+        // With a second package needing the old version there is nothing
+        // single to bump.
+        require(&mut graph, "app", "itoa@0.4.8", "^0.4");
+        assert!(matches!(
+            &itoa_versions(&graph).versions[0].hint,
+            Some(ConsolidationHint::None { message })
+                if message == "2 packages require itoa 0.4.8 and not 1.0.18: app@0.1.0, memchr@2.8.3"
+        ));
+    }
+}
@@ -1,11 +1,65 @@
 use anyhow::{Context, Result};
 use serde_json::{Value, json};
-use std::process::{Command, Stdio};
-use std::time::Instant;
-
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Instant, SystemTime};
+use tokio::process::Command;
+
+use super::audit::{audit, load_advisories};
+use super::bench::{
+    compare, is_valid_baseline_name, load_baseline, parse_libtest_benches,
+    read_criterion_estimates, save_baseline,
+};
+use super::context::{CancellationToken, ToolContext};
+use super::coverage::{
+    FileCoverage, LlvmTools, add_instrument_coverage, parse_lcov, test_executables,
+};
+use super::dependency_graph::{DependencyGraph, EdgeKinds, GraphFilter};
+use super::diagnostics::{diagnostics_result, parse_diagnostics};
+use super::duplicates::find_duplicates;
+use super::fixes::{SkippedFix, plan_fixes};
+use super::lints::{LintPolicy, LintSetting, effective_lints, manifest_lints};
+use super::metadata::{Metadata, Package, summarize_workspace};
+use super::outdated::find_outdated;
+use super::output::{append_text, command_result, finalize_result, structured_result};
+use super::process::{CommandOutput, run_command};
+use super::progress::ProgressReporter;
+use super::registry_index::{RegistryIndex, cargo_home};
+use super::state::ToolState;
+use super::test_results::{
+    FlakyTracker, TestReport, mark_ignored_tests, parse_test_counts, parse_test_list,
+    parse_test_report,
+};
+use super::workspace_queue::workspace_key;
+use crate::config::{ServerConfig, TestRunner};
+use crate::error::McpError;
 use crate::types::CargoToolParams;
 
-fn handle_clean(params: &CargoToolParams) -> Result<Value> {
+/// Result for a cargo invocation that ran but did not succeed. These are
+/// reported as tool results with `isError` set, not as JSON-RPC errors, so
+/// the caller can tell cargo's verdict apart from a broken server.
+fn failure_result(title: &str, output: &CommandOutput, ctx: &ToolContext) -> Value {
+    command_result(failure_text(title, output, ctx), output, json!({}))
+}
+
+fn failure_text(title: &str, output: &CommandOutput, ctx: &ToolContext) -> String {
+    if output.timed_out {
+        let seconds = ctx.timeout.map(|t| t.as_secs()).unwrap_or_default();
+        format!(
+            "{title} timed out after {seconds}s and was killed. Partial output:\n{}",
+            output.combined()
+        )
+    } else {
+        format!(
+            "{title} failed with exit code {}:\n{}",
+            output.exit_code().unwrap_or(-1),
+            output.combined()
+        )
+    }
+}
+
+async fn handle_clean(params: &CargoToolParams, ctx: &ToolContext) -> Result<Value> {
     let mut cmd = Command::new("cargo");
     cmd.arg("clean");
 
@@ -13,24 +67,22 @@ fn handle_clean(params: &CargoToolParams) -> Result<Value> {
         cmd.current_dir(working_dir);
     }
 
-    let output = cmd.output().context("Failed to execute cargo clean")?;
+    let output = run_command(cmd, ctx)
+        .await
+        .context("Failed to execute cargo clean")?;
 
-    let result = if output.status.success() {
-        "Clean completed successfully".to_string()
-    } else {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        format!("Clean failed: {}", stderr)
-    };
+    if !output.success() {
+        return Ok(failure_result("Clean", &output, ctx));
+    }
 
-    Ok(json!({
-        "content": [{
-            "type": "text",
-            "text": result
-        }]
-    }))
+    Ok(command_result(
+        "Clean completed successfully".to_string(),
+        &output,
+        json!({}),
+    ))
 }
 
-fn handle_search_crates(params: &CargoToolParams) -> Result<Value> {
+async fn handle_search_crates(params: &CargoToolParams, ctx: &ToolContext) -> Result<Value> {
     let mut cmd = Command::new("cargo");
     cmd.arg("search");
 
@@ -46,30 +98,24 @@ fn handle_search_crates(params: &CargoToolParams) -> Result<Value> {
         cmd.current_dir(working_dir);
     }
 
-    let output = cmd.output().context("Failed to execute cargo search")?;
+    let output = run_command(cmd, ctx)
+        .await
+        .context("Failed to execute cargo search")?;
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.success() {
+        return Ok(failure_result("Search", &output, ctx));
+    }
 
-    let result = if output.status.success() {
-        if stdout.is_empty() {
-            "No crates found".to_string()
-        } else {
-            stdout.to_string()
-        }
+    let result = if output.stdout.is_empty() {
+        "No crates found".to_string()
     } else {
-        format!("Search failed: {}", stderr)
+        output.stdout.clone()
     };
 
-    Ok(json!({
-        "content": [{
-            "type": "text",
-            "text": result
-        }]
-    }))
+    Ok(command_result(result, &output, json!({})))
 }
 
-fn handle_crate_info(params: &CargoToolParams) -> Result<Value> {
+async fn handle_crate_info(params: &CargoToolParams, ctx: &ToolContext) -> Result<Value> {
     let mut cmd = Command::new("cargo");
     cmd.arg("info");
 
@@ -81,26 +127,18 @@ fn handle_crate_info(params: &CargoToolParams) -> Result<Value> {
         cmd.current_dir(working_dir);
     }
 
-    let output = cmd.output().context("Failed to execute cargo info")?;
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
+    let output = run_command(cmd, ctx)
+        .await
+        .context("Failed to execute cargo info")?;
 
-    let result = if output.status.success() {
-        stdout.to_string()
-    } else {
-        format!("Info failed: {}", stderr)
-    };
+    if !output.success() {
+        return Ok(failure_result("Info", &output, ctx));
+    }
 
-    Ok(json!({
-        "content": [{
-            "type": "text",
-            "text": result
-        }]
-    }))
+    Ok(command_result(output.stdout.clone(), &output, json!({})))
 }
 
-fn handle_add_crate(params: &CargoToolParams) -> Result<Value> {
+async fn handle_add_crate(params: &CargoToolParams, ctx: &ToolContext) -> Result<Value> {
     let mut cmd = Command::new("cargo");
     cmd.arg("add");
 
@@ -122,26 +160,22 @@ fn handle_add_crate(params: &CargoToolParams) -> Result<Value> {
         cmd.current_dir(working_dir);
     }
 
-    let output = cmd.output().context("Failed to execute cargo add")?;
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
+    let output = run_command(cmd, ctx)
+        .await
+        .context("Failed to execute cargo add")?;
 
-    let result = if output.status.success() {
-        format!("Added crate successfully\n{}{}", stdout, stderr)
-    } else {
-        format!("Add crate failed: {}{}", stdout, stderr)
-    };
+    if !output.success() {
+        return Ok(failure_result("Add crate", &output, ctx));
+    }
 
-    Ok(json!({
-        "content": [{
-            "type": "text",
-            "text": result
-        }]
-    }))
+    Ok(command_result(
+        format!("Added crate successfully\n{}", output.combined()),
+        &output,
+        json!({}),
+    ))
 }
 
-fn handle_remove_crate(params: &CargoToolParams) -> Result<Value> {
+async fn handle_remove_crate(params: &CargoToolParams, ctx: &ToolContext) -> Result<Value> {
     let mut cmd = Command::new("cargo");
     cmd.arg("remove");
 
@@ -157,63 +191,1228 @@ fn handle_remove_crate(params: &CargoToolParams) -> Result<Value> {
         cmd.current_dir(working_dir);
     }
 
-    let output = cmd.output().context("Failed to execute cargo remove")?;
+    let output = run_command(cmd, ctx)
+        .await
+        .context("Failed to execute cargo remove")?;
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.success() {
+        return Ok(failure_result("Remove crate", &output, ctx));
+    }
 
-    let result = if output.status.success() {
-        format!("Removed crate successfully\n{}{}", stdout, stderr)
-    } else {
-        format!("Remove crate failed: {}{}", stdout, stderr)
-    };
+    Ok(command_result(
+        format!("Removed crate successfully\n{}", output.combined()),
+        &output,
+        json!({}),
+    ))
+}
 
-    Ok(json!({
-        "content": [{
-            "type": "text",
-            "text": result
-        }]
-    }))
+/// Whether the `cargo nextest` subcommand is installed.
+async fn nextest_available() -> bool {
+    Command::new("cargo")
+        .args(["nextest", "--version"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await
+        .is_ok_and(|status| status.success())
 }
 
-fn handle_test(params: &CargoToolParams) -> Result<Value> {
+/// `cargo test`, in libtest JSON mode when `structured` is set.
+fn cargo_test_command(
+    params: &CargoToolParams,
+    rerun: Option<&[String]>,
+    structured: bool,
+    no_fail_fast: bool,
+) -> Command {
     let mut cmd = Command::new("cargo");
     cmd.arg("test");
 
+    if no_fail_fast {
+        cmd.arg("--no-fail-fast");
+    }
+
+    if params.doc.unwrap_or(false) {
+        cmd.arg("--doc");
+    }
+
+    if let Some(package) = &params.package {
+        cmd.arg("-p").arg(package);
+    }
+
+    if let Some(working_dir) = &params.working_directory {
+        cmd.current_dir(working_dir);
+    }
+
+    // Arguments for the test binaries, passed after `--`.
+    let mut harness_args: Vec<String> = Vec::new();
+
+    if let Some(names) = rerun {
+        harness_args.push("--exact".to_string());
+        harness_args.extend(names.iter().cloned());
+    } else {
+        if let Some(test_name) = &params.test_name {
+            cmd.arg(test_name);
+        }
+        if params.exact.unwrap_or(false) {
+            harness_args.push("--exact".to_string());
+        }
+    }
+
+    if structured {
+        // libtest's JSON event format is still unstable on stable toolchains.
+        // Cargo does not fingerprint RUSTC_BOOTSTRAP, so this does not
+        // trigger a rebuild.
+        cmd.env("RUSTC_BOOTSTRAP", "1");
+        harness_args.extend(
+            [
+                "-Z",
+                "unstable-options",
+                "--format",
+                "json",
+                "--report-time",
+            ]
+            .map(String::from),
+        );
+    }
+
+    if !harness_args.is_empty() {
+        cmd.arg("--").args(&harness_args);
+    }
+
+    cmd
+}
+
+/// `cargo nextest run` with libtest-compatible JSON events on stdout.
+fn nextest_command(params: &CargoToolParams, rerun: Option<&[String]>) -> Command {
+    let mut cmd = Command::new("cargo");
+    cmd.args(["nextest", "run", "--message-format", "libtest-json"]);
+    cmd.env("NEXTEST_EXPERIMENTAL_LIBTEST_JSON", "1");
+
+    if params.no_fail_fast.unwrap_or(false) {
+        cmd.arg("--no-fail-fast");
+    }
+
     if let Some(package) = &params.package {
         cmd.arg("-p").arg(package);
     }
 
-    if let Some(test_name) = &params.test_name {
-        cmd.arg(test_name);
+    if let Some(working_dir) = &params.working_directory {
+        cmd.current_dir(working_dir);
+    }
+
+    // nextest has no --exact; exact matches are written as filtersets.
+    let exact_filter = |names: &[String]| {
+        names
+            .iter()
+            .map(|name| format!("test(={name})"))
+            .collect::<Vec<_>>()
+            .join(" | ")
+    };
+
+    if let Some(names) = rerun {
+        cmd.args(["-E", &exact_filter(names)]);
+    } else if let Some(test_name) = &params.test_name {
+        if params.exact.unwrap_or(false) {
+            cmd.args(["-E", &exact_filter(std::slice::from_ref(test_name))]);
+        } else {
+            cmd.arg(test_name);
+        }
+    }
+
+    cmd
+}
+
+async fn handle_test(
+    params: &CargoToolParams,
+    ctx: &ToolContext,
+    state: &ToolState,
+) -> Result<Value> {
+    let working_directory = params.working_directory.as_deref();
+    let package = params.package.as_deref();
+
+    let rerun = if params.rerun_failed.unwrap_or(false) {
+        let failed = state.failed_tests(working_directory, package);
+        if failed.is_empty() {
+            return Ok(structured_result(
+                "No failed tests remembered for this workspace and package; run `test` with `structured` first".to_string(),
+                true,
+                json!({ "success": true, "test_results": [] }),
+            ));
+        }
+        Some(failed)
+    } else {
+        None
+    };
+
+    let doc = params.doc.unwrap_or(false);
+    let nextest_installed =
+        ctx.test_runner == TestRunner::Nextest && !doc && nextest_available().await;
+    let (runner, note) = select_runner(ctx.test_runner, doc, nextest_installed);
+
+    // nextest always reports JSON events, and its cargo fallback keeps the
+    // same result shape. Reruns are always structured so the remembered
+    // failures stay current.
+    let structured = ctx.test_runner == TestRunner::Nextest
+        || doc
+        || rerun.is_some()
+        || params.structured.unwrap_or(false);
+    let cmd = match runner {
+        TestRunner::Cargo => cargo_test_command(
+            params,
+            rerun.as_deref(),
+            structured,
+            params.no_fail_fast.unwrap_or(false),
+        ),
+        TestRunner::Nextest => nextest_command(params, rerun.as_deref()),
+    };
+
+    let output = run_command(cmd, ctx)
+        .await
+        .with_context(|| format!("Failed to execute {}", runner_name(runner)))?;
+
+    let mut result = if structured {
+        let report = parse_test_report(&output.stdout, &output.stderr);
+        // A build failure or timeout says nothing about which tests pass.
+        if !report.suites.is_empty() && !output.timed_out {
+            let complete = rerun.is_none()
+                && params.test_name.is_none()
+                && !doc
+                && (output.success() || params.no_fail_fast.unwrap_or(false));
+            state.record_test_run(working_directory, package, &report, complete);
+        }
+        structured_test_result(&output, &report, runner, ctx)
+    } else {
+        let extra = match parse_test_counts(&output.stdout) {
+            Some(counts) => json!({ "tests": counts, "runner": runner.as_str() }),
+            None => json!({ "runner": runner.as_str() }),
+        };
+        let text = if output.success() {
+            output.combined()
+        } else {
+            failure_text("Tests", &output, ctx)
+        };
+        command_result(text, &output, extra)
+    };
+
+    if let Some(note) = note
+        && let Some(text) = result["content"][0]["text"].as_str()
+    {
+        result["content"][0]["text"] = json!(format!("{note}\n\n{text}"));
+    }
+
+    Ok(result)
+}
+
+/// The runner that actually runs the tests, with a note for the result
+/// when it falls back from the configured one.
+fn select_runner(
+    configured: TestRunner,
+    doc: bool,
+    nextest_installed: bool,
+) -> (TestRunner, Option<&'static str>) {
+    match configured {
+        TestRunner::Nextest if doc => (
+            TestRunner::Cargo,
+            Some("cargo-nextest does not run doctests; ran cargo test instead."),
+        ),
+        TestRunner::Nextest if !nextest_installed => (
+            TestRunner::Cargo,
+            Some("cargo-nextest is not installed; ran cargo test instead."),
+        ),
+        runner => (runner, None),
+    }
+}
+
+fn runner_name(runner: TestRunner) -> &'static str {
+    match runner {
+        TestRunner::Cargo => "cargo test",
+        TestRunner::Nextest => "cargo nextest run",
+    }
+}
+
+/// Run the selected tests repeatedly through the structured `cargo test`
+/// path and report how often each one passed.
+async fn handle_detect_flaky(params: &CargoToolParams, ctx: &ToolContext) -> Result<Value> {
+    let iterations = params.iterations.unwrap_or(10).max(1);
+    let shuffle = params.shuffle.unwrap_or(false);
+    let thread_counts = params
+        .thread_counts
+        .clone()
+        .filter(|counts| !counts.is_empty())
+        .or_else(|| params.test_threads.map(|threads| vec![threads]));
+
+    let mut tracker = FlakyTracker::default();
+    let mut runs = Vec::new();
+    let mut last_output = None;
+
+    for iteration in 1..=iterations {
+        // Every test binary has to run in every iteration, even after an
+        // earlier one failed.
+        let mut cmd = cargo_test_command(params, None, true, true);
+        if shuffle {
+            cmd.arg("--shuffle");
+        }
+        let test_threads = thread_counts
+            .as_ref()
+            .map(|counts| counts[(iteration as usize - 1) % counts.len()]);
+        if let Some(threads) = test_threads {
+            cmd.args(["--test-threads", &threads.to_string()]);
+        }
+
+        let output = run_command(cmd, ctx)
+            .await
+            .context("Failed to execute cargo test")?;
+        let report = parse_test_report(&output.stdout, &output.stderr);
+
+        // Nothing ran: the build is broken or cargo was killed, and further
+        // iterations would not tell us anything new.
+        if report.suites.is_empty() || output.timed_out {
+            return Ok(failure_result("Tests", &output, ctx));
+        }
+
+        let counts = report.counts();
+        runs.push(json!({
+            "iteration": iteration,
+            "test_threads": test_threads,
+            "passed": counts.passed,
+            "failed": counts.failed,
+        }));
+        tracker.record(iteration, &report);
+        last_output = Some(output);
+    }
+
+    let tests = tracker.finish();
+    let flaky: Vec<_> = tests.iter().filter(|t| t.flaky).collect();
+    let always_failing: Vec<_> = tests.iter().filter(|t| t.passed == 0).collect();
+
+    let mut text = format!(
+        "Ran {} tests {iterations} times: {} flaky, {} failed every run",
+        tests.len(),
+        flaky.len(),
+        always_failing.len()
+    );
+    for test in flaky.iter().chain(&always_failing) {
+        text.push_str(&format!(
+            "\n- {}: passed {}/{} ({} distinct failures)",
+            test.name,
+            test.passed,
+            test.runs,
+            test.failures.len()
+        ));
+    }
+
+    // The loop runs at least once and every iteration either returns or
+    // stores its output.
+    let output = last_output.context("No test iteration ran")?;
+    let success = flaky.is_empty() && always_failing.is_empty();
+    let mut result = command_result(
+        text,
+        &output,
+        json!({
+            "iterations": iterations,
+            "shuffle": shuffle,
+            "runs": runs,
+            "flaky_count": flaky.len(),
+            "always_failing_count": always_failing.len(),
+            "test_results": tests,
+        }),
+    );
+    // The last run may have passed even though earlier ones did not.
+    result["structuredContent"]["success"] = json!(success);
+    result["isError"] = json!(!success);
+
+    Ok(result)
+}
+
+/// Apply the machine-applicable suggestions of diagnostics remembered from
+/// the last compile or lint call, or just show the diff with `dry_run`.
+async fn handle_apply_fixes(params: &CargoToolParams, state: &ToolState) -> Result<Value> {
+    let working_directory = params.working_directory.as_deref();
+    let dry_run = params.dry_run.unwrap_or(false);
+    let ids = params.diagnostic_ids.as_deref().unwrap_or_default();
+    if ids.is_empty() {
+        return Ok(structured_result(
+            "No diagnostic_ids given".to_string(),
+            false,
+            json!({ "success": false }),
+        ));
+    }
+
+    let Some(remembered) = state.diagnostics(working_directory) else {
+        return Ok(structured_result(
+            "No diagnostics remembered for this workspace; run compile or lint first".to_string(),
+            false,
+            json!({ "success": false }),
+        ));
+    };
+
+    let mut selected = Vec::new();
+    let mut unknown = Vec::new();
+    for id in ids {
+        match remembered
+            .diagnostics
+            .iter()
+            .find(|diagnostic| diagnostic.id.as_ref() == Some(id))
+        {
+            Some(diagnostic) => selected.push(diagnostic),
+            None => unknown.push(id.clone()),
+        }
+    }
+
+    // Span paths are relative to the workspace root, where cargo runs rustc.
+    let base = workspace_key(working_directory);
+    let mut plan = plan_fixes(&base, &selected, remembered.recorded_at);
+    plan.skipped
+        .extend(unknown.into_iter().map(|id| SkippedFix {
+            id,
+            reason: "not among the diagnostics of the last compile or lint call".to_string(),
+        }));
+
+    if !dry_run {
+        for file in &plan.files {
+            std::fs::write(&file.path, &file.contents)
+                .with_context(|| format!("Failed to write {}", file.file))?;
+        }
+    }
+
+    let verb = if dry_run { "Would apply" } else { "Applied" };
+    let mut text = format!(
+        "{verb} {} of {} fixes in {} files",
+        plan.applied.len(),
+        ids.len(),
+        plan.files.len()
+    );
+    for skipped in &plan.skipped {
+        text.push_str(&format!("\n- skipped {}: {}", skipped.id, skipped.reason));
+    }
+    let diff = plan.diff();
+    if !diff.is_empty() {
+        text.push_str("\n\n");
+        text.push_str(&diff);
+    }
+
+    let success = !plan.applied.is_empty();
+    Ok(structured_result(
+        text,
+        success,
+        json!({
+            "success": success,
+            "dry_run": dry_run,
+            "applied": plan.applied,
+            "skipped": plan.skipped,
+            "files": plan.files,
+            "diff": diff,
+        }),
+    ))
+}
+
+/// Run `cargo metadata` for the working directory's workspace, limited to
+/// the workspace members unless `with_dependencies` is set. The resolve
+/// then follows the requested features and target platform. When cargo
+/// itself fails, e.g. on a broken manifest, its output is returned so the
+/// caller can report it as a tool failure.
+async fn cargo_metadata(
+    params: &CargoToolParams,
+    ctx: &ToolContext,
+    with_dependencies: bool,
+) -> Result<Result<Metadata, CommandOutput>> {
+    let mut cmd = Command::new("cargo");
+    cmd.args(["metadata", "--format-version", "1"]);
+    if with_dependencies {
+        if let Some(features) = &params.features {
+            cmd.args(["--features", &features.join(",")]);
+        }
+        if params.all_features.unwrap_or(false) {
+            cmd.arg("--all-features");
+        }
+        if params.no_default_features.unwrap_or(false) {
+            cmd.arg("--no-default-features");
+        }
+        if let Some(target) = params.target.as_deref().filter(|target| *target != "all") {
+            cmd.args(["--filter-platform", target]);
+        }
+    } else {
+        cmd.arg("--no-deps");
+    }
+    if let Some(working_dir) = &params.working_directory {
+        cmd.current_dir(working_dir);
+    }
+    let output = run_command(cmd, ctx)
+        .await
+        .context("Failed to execute cargo metadata")?;
+    if !output.success() {
+        return Ok(Err(output));
+    }
+
+    let metadata =
+        serde_json::from_str(&output.stdout).context("Failed to parse cargo metadata")?;
+    Ok(Ok(metadata))
+}
+
+/// The members a cargo command run with these parameters operates on: the
+/// requested package, the package containing the working directory, or the
+/// workspace's default members.
+fn selected_members<'a>(metadata: &'a Metadata, params: &CargoToolParams) -> Vec<&'a Package> {
+    if let Some(package) = &params.package {
+        return metadata
+            .members()
+            .filter(|member| &member.name == package)
+            .collect();
+    }
+
+    let working_dir = params
+        .working_directory
+        .as_deref()
+        .map(PathBuf::from)
+        .or_else(|| std::env::current_dir().ok())
+        .map(|dir| dir.canonicalize().unwrap_or(dir));
+    let enclosing = working_dir.and_then(|dir| {
+        metadata
+            .members()
+            .filter(|member| {
+                member
+                    .manifest_path
+                    .parent()
+                    .is_some_and(|member_dir| dir.starts_with(member_dir))
+            })
+            .max_by_key(|member| member.manifest_path.components().count())
+    });
+    match enclosing {
+        Some(member) => vec![member],
+        None => metadata
+            .members()
+            .filter(|member| metadata.is_default_member(member))
+            .collect(),
+    }
+}
+
+/// The resolved dependency graph as nodes and edges, filtered like
+/// `cargo tree`, or every path to one package for `why`.
+async fn handle_dependency_graph(params: &CargoToolParams, ctx: &ToolContext) -> Result<Value> {
+    let kinds = match params.edges.as_deref().map(EdgeKinds::parse).transpose() {
+        Ok(kinds) => kinds.unwrap_or_default(),
+        Err(message) => {
+            return Ok(structured_result(
+                message,
+                false,
+                json!({ "success": false }),
+            ));
+        }
+    };
+    let metadata = match cargo_metadata(params, ctx, true).await? {
+        Ok(metadata) => metadata,
+        Err(output) => return Ok(failure_result("Reading metadata", &output, ctx)),
+    };
+    let mut graph = DependencyGraph::from_metadata(&metadata)?;
+    graph.roots = match &params.package {
+        Some(package) => graph.find(package),
+        None => selected_members(&metadata, params)
+            .into_iter()
+            .map(|member| member.id.clone())
+            .collect(),
+    };
+    if graph.roots.is_empty() {
+        return Ok(structured_result(
+            format!(
+                "{} is not in the dependency graph",
+                params.package.as_deref().unwrap_or("The working directory")
+            ),
+            false,
+            json!({ "success": false }),
+        ));
+    }
+
+    let unknown: Vec<&str> = [&params.invert, &params.prune]
+        .into_iter()
+        .flatten()
+        .flatten()
+        .chain(&params.why)
+        .filter(|spec| graph.find(spec).is_empty())
+        .map(String::as_str)
+        .collect();
+    if !unknown.is_empty() {
+        return Ok(structured_result(
+            format!("Not in the dependency graph: {}", unknown.join(", ")),
+            false,
+            json!({ "success": false }),
+        ));
+    }
+
+    if let Some(why) = &params.why {
+        let limit = params.limit.unwrap_or(100) as usize;
+        let (paths, truncated) = graph.paths_to(why, kinds, limit);
+        let labeled: Vec<Vec<String>> = paths
+            .iter()
+            .map(|path| path.iter().map(|id| graph.label(id)).collect())
+            .collect();
+
+        let mut text = if paths.is_empty() {
+            format!("{why} is not a dependency of {}", roots_text(&graph))
+        } else {
+            format!(
+                "{why} is reached through {} path{}{}:",
+                paths.len(),
+                if paths.len() == 1 { "" } else { "s" },
+                if truncated { " (truncated)" } else { "" }
+            )
+        };
+        for path in &labeled {
+            text.push_str(&format!("\n  {}", path.join(" -> ")));
+        }
+
+        let mut structured = serde_json::to_value(graph.along(&paths, kinds))?;
+        structured["success"] = json!(true);
+        structured["paths"] = json!(labeled);
+        structured["truncated"] = json!(truncated);
+        return Ok(structured_result(text, true, structured));
+    }
+
+    let filter = GraphFilter {
+        kinds,
+        invert: params.invert.clone().unwrap_or_default(),
+        prune: params.prune.clone().unwrap_or_default(),
+        depth: params.depth,
+        duplicates: params.duplicates.unwrap_or(false),
+    };
+    let filtered = graph.filter(&filter);
+    let text = format!(
+        "Dependency graph of {}: {} packages, {} edges",
+        roots_text(&filtered),
+        filtered.nodes.len(),
+        filtered.edges.len()
+    );
+    let mut structured = serde_json::to_value(&filtered)?;
+    structured["success"] = json!(true);
+    Ok(structured_result(text, true, structured))
+}
+
+fn roots_text(graph: &DependencyGraph) -> String {
+    graph
+        .roots
+        .iter()
+        .map(|id| graph.label(id))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Packages resolved to several versions, what pulls each version in,
+/// and how the versions could be unified.
+async fn handle_duplicate_dependencies(
+    params: &CargoToolParams,
+    ctx: &ToolContext,
+) -> Result<Value> {
+    let kinds = match params.edges.as_deref().map(EdgeKinds::parse).transpose() {
+        Ok(kinds) => kinds.unwrap_or_default(),
+        Err(message) => {
+            return Ok(structured_result(
+                message,
+                false,
+                json!({ "success": false }),
+            ));
+        }
+    };
+    let metadata = match cargo_metadata(params, ctx, true).await? {
+        Ok(metadata) => metadata,
+        Err(output) => return Ok(failure_result("Reading metadata", &output, ctx)),
+    };
+    let graph = DependencyGraph::from_metadata(&metadata)?.filter(&GraphFilter {
+        kinds,
+        ..GraphFilter::default()
+    });
+    let duplicates = find_duplicates(&graph);
+
+    let mut text = match duplicates.len() {
+        0 => "No package is resolved to more than one version".to_string(),
+        1 => "1 package is resolved to more than one version:".to_string(),
+        count => format!("{count} packages are resolved to more than one version:"),
+    };
+    for duplicate in &duplicates {
+        text.push_str(&format!("\n\n{}", duplicate.summary()));
+    }
+
+    Ok(structured_result(
+        text,
+        true,
+        json!({
+            "success": true,
+            "duplicate_count": duplicates.len(),
+            "duplicates": duplicates,
+        }),
+    ))
+}
+
+/// Newer versions of the locked dependencies, looked up in the local
+/// registry index cache so it works without network access.
+async fn handle_outdated(params: &CargoToolParams, ctx: &ToolContext) -> Result<Value> {
+    let Some(mut index) = RegistryIndex::open() else {
+        return Ok(structured_result(
+            "No local registry index found under $CARGO_HOME/registry/index; run a cargo command that fetches the index first".to_string(),
+            false,
+            json!({ "success": false }),
+        ));
+    };
+    let metadata = match cargo_metadata(params, ctx, true).await? {
+        Ok(metadata) => metadata,
+        Err(output) => return Ok(failure_result("Reading metadata", &output, ctx)),
+    };
+    let graph = DependencyGraph::from_metadata(&metadata)?;
+    if let Some(dependency) = &params.dependency
+        && graph.find(dependency).is_empty()
+    {
+        return Ok(structured_result(
+            format!("{dependency} is not in Cargo.lock"),
+            false,
+            json!({ "success": false }),
+        ));
+    }
+
+    let report = find_outdated(&graph, &mut index, params.dependency.as_deref());
+    let mut structured = serde_json::to_value(&report)?;
+    structured["success"] = json!(true);
+    structured["index"] = json!(index.root());
+    Ok(structured_result(report.summary(), true, structured))
+}
+
+/// Check Cargo.lock against a local RustSec advisory database. Nothing is
+/// fetched: the database is a checkout the user keeps up to date.
+async fn handle_audit(params: &CargoToolParams, ctx: &ToolContext) -> Result<Value> {
+    let db = ctx
+        .advisory_db
+        .clone()
+        .or_else(|| cargo_home().map(|home| home.join("advisory-db")));
+    let Some(db) = db.filter(|db| db.is_dir()) else {
+        let location = ctx.advisory_db.as_ref().map_or_else(
+            || "$CARGO_HOME/advisory-db".to_string(),
+            |db| db.display().to_string(),
+        );
+        return Ok(structured_result(
+            format!(
+                "No advisory database at {location}; clone https://github.com/rustsec/advisory-db and pass its path as advisory_db or --advisory-db"
+            ),
+            false,
+            json!({ "success": false }),
+        ));
+    };
+    let metadata = match cargo_metadata(params, ctx, true).await? {
+        Ok(metadata) => metadata,
+        Err(output) => return Ok(failure_result("Reading metadata", &output, ctx)),
+    };
+    let graph = DependencyGraph::from_metadata(&metadata)?;
+
+    let names: BTreeSet<&str> = graph.nodes.iter().map(|node| node.name.as_str()).collect();
+    let advisories = match load_advisories(&db, &names) {
+        Ok(advisories) => advisories,
+        Err(error) => {
+            return Ok(structured_result(
+                format!("Failed to read the advisory database: {error:#}"),
+                false,
+                json!({ "success": false }),
+            ));
+        }
+    };
+    let mut index = RegistryIndex::open();
+    let findings = audit(&graph, &advisories, index.as_mut());
+
+    let mut text = format!(
+        "Checked {} locked packages against {}: {} advisor{} appl{}",
+        graph.nodes.len(),
+        db.display(),
+        findings.len(),
+        if findings.len() == 1 { "y" } else { "ies" },
+        if findings.len() == 1 { "ies" } else { "y" }
+    );
+    for finding in &findings {
+        text.push_str(&format!("\n\n{}", finding.summary()));
     }
+    let structured = json!({
+        "success": true,
+        "advisory_db": db,
+        "packages_checked": graph.nodes.len(),
+        "advisories_checked": advisories.len(),
+        "findings": findings,
+    });
+    Ok(structured_result(text, true, structured))
+}
+
+async fn handle_workspace_info(params: &CargoToolParams, ctx: &ToolContext) -> Result<Value> {
+    let metadata = match cargo_metadata(params, ctx, false).await? {
+        Ok(metadata) => metadata,
+        Err(output) => return Ok(failure_result("Reading metadata", &output, ctx)),
+    };
 
+    let mut summary = summarize_workspace(&metadata);
+    if let Some(package) = &params.package {
+        summary.members.retain(|member| &member.name == package);
+        if summary.members.is_empty() {
+            return Ok(structured_result(
+                format!("{package} is not a member of the workspace"),
+                false,
+                json!({ "success": false }),
+            ));
+        }
+    }
+
+    let mut structured = serde_json::to_value(&summary)?;
+    structured["success"] = json!(true);
+    Ok(structured_result(summary.outline(), true, structured))
+}
+
+/// Run the benchmarks, collect libtest and Criterion results, and compare
+/// them with a saved baseline.
+async fn handle_bench(params: &CargoToolParams, ctx: &ToolContext) -> Result<Value> {
+    for name in [&params.baseline, &params.save_baseline]
+        .into_iter()
+        .flatten()
+    {
+        if !is_valid_baseline_name(name) {
+            return Ok(structured_result(
+                format!("Invalid baseline name {name:?}: use letters, digits, '-', '_' and '.'"),
+                false,
+                json!({ "success": false }),
+            ));
+        }
+    }
+
+    let metadata = match cargo_metadata(params, ctx, false).await? {
+        Ok(metadata) => metadata,
+        Err(output) => return Ok(failure_result("Reading metadata", &output, ctx)),
+    };
+    let baseline_dir = metadata
+        .target_directory
+        .join("cargo-mcp")
+        .join("bench-baselines");
+    let baseline = match &params.baseline {
+        Some(name) => match load_baseline(&baseline_dir, name) {
+            Some(baseline) => Some(baseline),
+            None => {
+                return Ok(structured_result(
+                    format!("No saved baseline named {name:?}; save one with save_baseline first"),
+                    false,
+                    json!({ "success": false }),
+                ));
+            }
+        },
+        None => None,
+    };
+
+    let started = SystemTime::now();
+    let mut cmd = cargo_command("bench", params);
+    if let Some(filter) = &params.test_name {
+        cmd.arg(filter);
+    }
+    let output = run_command(cmd, ctx)
+        .await
+        .context("Failed to execute cargo bench")?;
+    if !output.success() {
+        return Ok(failure_result("Benchmarks", &output, ctx));
+    }
+
+    let mut results = parse_libtest_benches(&output.stdout);
+    results.extend(read_criterion_estimates(
+        &metadata.target_directory.join("criterion"),
+        started,
+    ));
+
+    if let Some(name) = &params.save_baseline {
+        save_baseline(&baseline_dir, name, &results)
+            .with_context(|| format!("Failed to save baseline {name:?}"))?;
+    }
+
+    let threshold = params.regression_threshold.unwrap_or(5.0);
+    let comparisons = compare(results, baseline.as_ref(), threshold);
+    let regressions = comparisons.iter().filter(|c| c.regression).count();
+
+    let mut text = format!("Ran {} benchmarks", comparisons.len());
+    if let Some(name) = &params.baseline {
+        text.push_str(&format!(
+            "; {regressions} regressed more than {threshold}% against baseline {name:?}"
+        ));
+    }
+    for comparison in &comparisons {
+        text.push_str(&format!(
+            "\n- {}: {:.2} ns/iter",
+            comparison.result.name, comparison.result.ns_per_iter
+        ));
+        if let Some(change) = comparison.change_percent {
+            text.push_str(&format!(" ({change:+.2}%)"));
+        }
+        if comparison.regression {
+            text.push_str(" REGRESSION");
+        }
+    }
+    if let Some(name) = &params.save_baseline {
+        text.push_str(&format!("\nSaved as baseline {name:?}"));
+    }
+
+    Ok(command_result(
+        text,
+        &output,
+        json!({
+            "benchmarks": comparisons,
+            "baseline": params.baseline,
+            "regression_threshold": threshold,
+            "regression_count": regressions,
+        }),
+    ))
+}
+
+/// Print a value from `rustc` as run in the working directory, so a
+/// `rust-toolchain.toml` there picks the toolchain. A failing rustc
+/// returns its output for the caller to report.
+async fn rustc_query(
+    args: &[&str],
+    params: &CargoToolParams,
+    ctx: &ToolContext,
+) -> Result<Result<String, CommandOutput>> {
+    let mut cmd = Command::new("rustc");
+    cmd.args(args);
     if let Some(working_dir) = &params.working_directory {
         cmd.current_dir(working_dir);
     }
+    let output = run_command(cmd, ctx)
+        .await
+        .context("Failed to execute rustc")?;
+    if !output.success() {
+        return Ok(Err(output));
+    }
+    Ok(Ok(output.stdout))
+}
+
+/// Run the tests with `-C instrument-coverage` and summarize the profiles
+/// with the toolchain's llvm-tools. The instrumented build goes to its own
+/// target directory so it does not invalidate the regular one.
+async fn handle_coverage(params: &CargoToolParams, ctx: &ToolContext) -> Result<Value> {
+    let sysroot = match rustc_query(&["--print", "sysroot"], params, ctx).await? {
+        Ok(sysroot) => sysroot,
+        Err(output) => return Ok(failure_result("Locating the sysroot", &output, ctx)),
+    };
+    let version = match rustc_query(&["-vV"], params, ctx).await? {
+        Ok(version) => version,
+        Err(output) => return Ok(failure_result("Querying rustc", &output, ctx)),
+    };
+    let host = version
+        .lines()
+        .find_map(|line| line.strip_prefix("host: "))
+        .context("rustc -vV did not report the host triple")?;
+    let Some(tools) = LlvmTools::locate(&sysroot, host) else {
+        return Ok(structured_result(
+            "llvm-tools is not installed for this toolchain, so coverage cannot be collected. Install it with `rustup component add llvm-tools` and try again.".to_string(),
+            false,
+            json!({ "success": false }),
+        ));
+    };
+
+    let metadata = match cargo_metadata(params, ctx, false).await? {
+        Ok(metadata) => metadata,
+        Err(output) => return Ok(failure_result("Reading metadata", &output, ctx)),
+    };
+    let workspace_root = metadata.workspace_root;
+    let coverage_dir = metadata.target_directory.join("cargo-mcp").join("coverage");
+
+    // Profiles left over from an earlier run would be merged into this one.
+    let profile_dir = coverage_dir.join("profiles");
+    if profile_dir.exists() {
+        std::fs::remove_dir_all(&profile_dir).context("Failed to clear old coverage profiles")?;
+    }
+    std::fs::create_dir_all(&profile_dir).context("Failed to create the coverage directory")?;
+
+    let mut cmd = cargo_command("test", params);
+    cmd.args(["--no-fail-fast", "--message-format=json", "--target-dir"])
+        .arg(coverage_dir.join("target"));
+    add_instrument_coverage(
+        &mut cmd,
+        Path::new(params.working_directory.as_deref().unwrap_or(".")),
+        params.target.as_deref().unwrap_or(host),
+    );
+    cmd.env(
+        "LLVM_PROFILE_FILE",
+        profile_dir.join("cargo-mcp-%p-%m.profraw"),
+    );
+    let test_output = run_command(cmd, ctx)
+        .await
+        .context("Failed to execute cargo test")?;
+
+    let executables = test_executables(&test_output.stdout);
+    let profiles: Vec<PathBuf> = std::fs::read_dir(&profile_dir)
+        .context("Failed to read the coverage directory")?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "profraw"))
+        .collect();
+    if test_output.timed_out || executables.is_empty() || profiles.is_empty() {
+        return Ok(failure_result("Coverage test run", &test_output, ctx));
+    }
 
-    let output = cmd.output().context("Failed to execute cargo test")?;
+    let profdata = coverage_dir.join("coverage.profdata");
+    let mut cmd = Command::new(&tools.profdata);
+    cmd.args(["merge", "-sparse"])
+        .args(&profiles)
+        .arg("-o")
+        .arg(&profdata);
+    let output = run_command(cmd, ctx)
+        .await
+        .context("Failed to execute llvm-profdata")?;
+    if !output.success() {
+        return Ok(failure_result("Merging coverage profiles", &output, ctx));
+    }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
+    let mut cmd = Command::new(&tools.cov);
+    cmd.args(["export", "-format=lcov"])
+        .arg(format!("-instr-profile={}", profdata.display()))
+        .arg("-ignore-filename-regex=/\\.cargo/(registry|git)/|^/rustc/")
+        .arg(&executables[0]);
+    for executable in &executables[1..] {
+        cmd.arg("-object").arg(executable);
+    }
+    let output = run_command(cmd, ctx)
+        .await
+        .context("Failed to execute llvm-cov")?;
+    if !output.success() {
+        return Ok(failure_result("Exporting coverage", &output, ctx));
+    }
 
-    let result = if output.status.success() {
-        format!("{}{}", stdout, stderr)
+    // Only report the workspace's own sources, relative to its root.
+    let files: Vec<FileCoverage> = parse_lcov(&output.stdout)
+        .into_iter()
+        .filter_map(|mut file| {
+            let relative = Path::new(&file.file).strip_prefix(&workspace_root).ok()?;
+            file.file = relative.display().to_string();
+            Some(file)
+        })
+        .collect();
+
+    let totals = files
+        .iter()
+        .fold(FileCoverage::default(), |mut totals, file| {
+            totals.lines_covered += file.lines_covered;
+            totals.lines_total += file.lines_total;
+            totals.functions_covered += file.functions_covered;
+            totals.functions_total += file.functions_total;
+            totals
+        });
+    let summary = |file: &FileCoverage| {
+        json!({
+            "file": file.file,
+            "lines_covered": file.lines_covered,
+            "lines_total": file.lines_total,
+            "line_percent": file.line_percent(),
+            "functions_covered": file.functions_covered,
+            "functions_total": file.functions_total,
+            "function_percent": file.function_percent(),
+        })
+    };
+
+    let mut text = format!(
+        "Coverage: {}% of lines ({}/{}), {}% of functions ({}/{}) in {} files",
+        totals.line_percent(),
+        totals.lines_covered,
+        totals.lines_total,
+        totals.function_percent(),
+        totals.functions_covered,
+        totals.functions_total,
+        files.len()
+    );
+    if !test_output.success() {
+        text.push_str("\nSome tests failed; coverage reflects the tests that ran.");
+    }
+    for file in &files {
+        text.push_str(&format!(
+            "\n- {}: {}% lines, {}% functions",
+            file.file,
+            file.line_percent(),
+            file.function_percent()
+        ));
+    }
+
+    let mut totals = summary(&totals);
+    if let Some(totals) = totals.as_object_mut() {
+        totals.remove("file");
+    }
+    let mut extra = json!({
+        "totals": totals,
+        "files": files.iter().map(summary).collect::<Vec<_>>(),
+    });
+    if let Some(counts) = parse_test_counts(&test_output.stdout) {
+        extra["tests"] = json!(counts);
+    }
+    if let Some(wanted) = &params.file {
+        let wanted = wanted.trim_start_matches("./");
+        match files
+            .iter()
+            .find(|file| file.file == wanted || file.file.ends_with(&format!("/{wanted}")))
+        {
+            Some(file) => {
+                let ranges = file.uncovered_ranges();
+                text.push_str(&format!("\n\nUncovered lines in {}:", file.file));
+                for (start, end) in &ranges {
+                    if start == end {
+                        text.push_str(&format!(" {start}"));
+                    } else {
+                        text.push_str(&format!(" {start}-{end}"));
+                    }
+                }
+                if ranges.is_empty() {
+                    text.push_str(" none");
+                }
+                extra["uncovered"] = json!({
+                    "file": file.file,
+                    "ranges": ranges
+                        .iter()
+                        .map(|(start, end)| json!({ "start": start, "end": end }))
+                        .collect::<Vec<_>>(),
+                });
+            }
+            None => text.push_str(&format!("\n\nNo coverage data for {wanted}")),
+        }
+    }
+
+    Ok(command_result(text, &test_output, extra))
+}
+
+/// Result of a test run in libtest JSON mode: per-test results and
+/// per-binary totals, with the failures and their output in the summary.
+fn structured_test_result(
+    output: &CommandOutput,
+    report: &TestReport,
+    runner: TestRunner,
+    ctx: &ToolContext,
+) -> Value {
+    let counts = report.counts();
+
+    // Nothing ran: the build failed or cargo was killed.
+    let text = if report.suites.is_empty() && !output.success() {
+        failure_text("Tests", output, ctx)
     } else {
-        format!("Tests failed:\n{}{}", stdout, stderr)
+        let status = if output.success() { "passed" } else { "failed" };
+        let mut text = format!(
+            "Tests {status}: {} passed, {} failed, {} ignored, {} filtered out",
+            counts.passed, counts.failed, counts.ignored, counts.filtered_out
+        );
+        for failure in report.failures() {
+            if let Some(doctest) = &failure.doctest {
+                text.push_str(&format!(
+                    "\n\n--- doctest {} ({}:{})",
+                    doctest.item, doctest.file, doctest.line
+                ));
+            } else {
+                text.push_str(&format!("\n\n--- {}", failure.name));
+                if let Some(target) = &failure.target {
+                    text.push_str(&format!(" ({target})"));
+                }
+            }
+            text.push_str(" ---\n");
+            text.push_str(failure.stdout.as_deref().unwrap_or_default().trim_end());
+        }
+        if output.timed_out {
+            text.push_str("\n\n");
+            text.push_str(&failure_text("Tests", output, ctx));
+        }
+        text
     };
 
-    Ok(json!({
-        "content": [{
-            "type": "text",
-            "text": result
-        }]
-    }))
+    command_result(
+        text,
+        output,
+        json!({
+            "runner": runner.as_str(),
+            "tests": counts,
+            "suites": report.suites,
+            "test_results": report.tests,
+        }),
+    )
 }
 
-fn handle_lint(params: &CargoToolParams) -> Result<Value> {
+/// Run the test binaries in `--list` mode, once more with `--ignored` to learn
+/// which of the listed tests are ignored.
+async fn handle_list_tests(params: &CargoToolParams, ctx: &ToolContext) -> Result<Value> {
+    let list = |ignored: bool| {
+        let mut cmd = cargo_command("test", params);
+        if let Some(test_name) = &params.test_name {
+            cmd.arg(test_name);
+        }
+        cmd.args(["--", "--list"]);
+        if ignored {
+            cmd.arg("--ignored");
+        }
+        run_command(cmd, ctx)
+    };
+
+    let output = list(false).await.context("Failed to list tests")?;
+    if !output.success() {
+        return Ok(failure_result("Listing tests", &output, ctx));
+    }
+    let ignored_output = list(true).await.context("Failed to list ignored tests")?;
+    if !ignored_output.success() {
+        return Ok(failure_result(
+            "Listing ignored tests",
+            &ignored_output,
+            ctx,
+        ));
+    }
+
+    let mut targets = parse_test_list(&output.stdout, &output.stderr);
+    let ignored_targets = parse_test_list(&ignored_output.stdout, &ignored_output.stderr);
+    mark_ignored_tests(&mut targets, &ignored_targets);
+
+    let test_count: usize = targets.iter().map(|target| target.tests.len()).sum();
+    let ignored_count: usize = targets
+        .iter()
+        .flat_map(|target| &target.tests)
+        .filter(|test| test.ignored)
+        .count();
+
+    let mut text = format!(
+        "Found {test_count} tests ({ignored_count} ignored) in {} targets",
+        targets.len()
+    );
+    for target in &targets {
+        text.push_str(&format!(
+            "\n\n{}:",
+            target.target.as_deref().unwrap_or("unknown target")
+        ));
+        for test in &target.tests {
+            text.push_str(&format!("\n  {}", test.name));
+            if test.kind != "test" {
+                text.push_str(&format!(" [{}]", test.kind));
+            }
+            if test.ignored {
+                text.push_str(" (ignored)");
+            }
+        }
+    }
+
+    Ok(command_result(
+        text,
+        &output,
+        json!({
+            "targets": targets,
+            "test_count": test_count,
+            "ignored_count": ignored_count,
+        }),
+    ))
+}
+
+async fn handle_lint(
+    params: &CargoToolParams,
+    ctx: &ToolContext,
+    state: &ToolState,
+) -> Result<Value> {
+    let policy = match LintPolicy::from_params(params) {
+        Ok(policy) => policy,
+        Err(message) => {
+            return Ok(structured_result(
+                message,
+                false,
+                json!({ "success": false }),
+            ));
+        }
+    };
+    let lint_levels = read_lint_levels(params, ctx, &policy).await;
+
+    let started = SystemTime::now();
     let mut cmd = Command::new("cargo");
     cmd.arg("clippy");
+    cmd.arg("--message-format=json");
 
     // Always include tests unless explicitly disabled
     if !params.no_tests.unwrap_or(false) {
@@ -231,45 +1430,94 @@ fn handle_lint(params: &CargoToolParams) -> Result<Value> {
     }
 
     // Build clippy arguments
-    cmd.arg("--");
+    let clippy_args = policy.clippy_args();
+    if !clippy_args.is_empty() {
+        cmd.arg("--").args(clippy_args);
+    }
 
-    let level = if params.warn_only.unwrap_or(false) {
-        "-W"
-    } else {
-        "-D"
-    };
-    cmd.arg(format!("{}clippy::pedantic", level));
+    let output = run_command(cmd, ctx)
+        .await
+        .context("Failed to execute cargo clippy")?;
 
-    if params.ignore_docs.unwrap_or(false) {
-        cmd.arg("-Aclippy::missing_docs");
+    if output.timed_out {
+        return Ok(failure_result("Linting", &output, ctx));
     }
 
-    let output = cmd.output().context("Failed to execute cargo clippy")?;
+    let mut diagnostics = parse_diagnostics(&output.stdout);
+    state.remember_diagnostics(
+        params.working_directory.as_deref(),
+        &mut diagnostics,
+        started,
+    );
+
+    let mut result = diagnostics_result("Linting", &output, &diagnostics);
+    match lint_levels {
+        Ok(lint_levels) => {
+            append_text(&mut result, &lint_levels_text(&lint_levels));
+            result["structuredContent"]["lint_levels"] = lint_levels
+                .iter()
+                .map(|(package, lints)| json!({ "package": package, "lints": lints }))
+                .collect();
+        }
+        Err(error) => {
+            append_text(
+                &mut result,
+                &format!("\n\nLint levels in effect could not be read: {error}"),
+            );
+            result["structuredContent"]["lint_levels_error"] = json!(error);
+        }
+    }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
+    Ok(result)
+}
 
-    let result = if output.status.success() {
-        if stdout.is_empty() && stderr.is_empty() {
-            "Linting completed successfully".to_string()
-        } else {
-            format!("{}{}", stdout, stderr)
-        }
-    } else {
-        format!("Linting failed:\n{}{}", stdout, stderr)
+/// The lint levels in effect for each selected member. They are only
+/// reported alongside clippy's findings, so a manifest that cannot be read
+/// is returned as a message instead of failing the lint run.
+async fn read_lint_levels(
+    params: &CargoToolParams,
+    ctx: &ToolContext,
+    policy: &LintPolicy,
+) -> Result<Vec<(String, Vec<LintSetting>)>, String> {
+    let metadata = match cargo_metadata(params, ctx, false).await {
+        Ok(Ok(metadata)) => metadata,
+        Ok(Err(output)) => return Err(format!("cargo metadata failed: {}", output.stderr.trim())),
+        Err(error) => return Err(format!("{error:#}")),
     };
+    let workspace_manifest = metadata.workspace_root.join("Cargo.toml");
+    let mut lint_levels = Vec::new();
+    for member in selected_members(&metadata, params) {
+        let manifest = manifest_lints(&member.manifest_path, &workspace_manifest)
+            .map_err(|error| format!("{error:#}"))?;
+        lint_levels.push((member.name.clone(), effective_lints(manifest, policy)));
+    }
+    Ok(lint_levels)
+}
 
-    Ok(json!({
-        "content": [{
-            "type": "text",
-            "text": result
-        }]
-    }))
+fn lint_levels_text(lint_levels: &[(String, Vec<LintSetting>)]) -> String {
+    let mut text = String::from("\n\nLint levels in effect:");
+    for (package, lints) in lint_levels {
+        if lints.is_empty() {
+            text.push_str(&format!("\n{package}: defaults"));
+            continue;
+        }
+        text.push_str(&format!("\n{package}:"));
+        for lint in lints {
+            text.push_str(&format!("\n  {lint}"));
+        }
+    }
+    text
 }
 
-fn handle_pre_build(params: &CargoToolParams) -> Result<Value> {
+async fn handle_pre_build(
+    params: &CargoToolParams,
+    ctx: &ToolContext,
+    state: &ToolState,
+) -> Result<Value> {
+    let started = SystemTime::now();
     let mut cmd = Command::new("cargo");
     cmd.arg("check");
+    cmd.arg("--message-format=json");
     cmd.env("CARGO_BUILD_WARNINGS", "allow");
 
     // Always include tests unless explicitly disabled
@@ -287,35 +1535,108 @@ fn handle_pre_build(params: &CargoToolParams) -> Result<Value> {
         cmd.current_dir(working_dir);
     }
 
-    let output = cmd.output().context("Failed to execute cargo check")?;
+    let output = run_command(cmd, ctx)
+        .await
+        .context("Failed to execute cargo check")?;
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
+    if output.timed_out {
+        return Ok(failure_result("Pre-build check", &output, ctx));
+    }
 
-    let result = if output.status.success() {
-        if stdout.is_empty() && stderr.is_empty() {
-            "Pre-build check completed successfully".to_string()
-        } else {
-            format!("{}{}", stdout, stderr)
+    let mut diagnostics = parse_diagnostics(&output.stdout);
+    state.remember_diagnostics(
+        params.working_directory.as_deref(),
+        &mut diagnostics,
+        started,
+    );
+
+    let mut result = diagnostics_result("Pre-build check", &output, &diagnostics);
+    if params.explain_errors.unwrap_or(false) {
+        let mut codes: Vec<&str> = diagnostics
+            .iter()
+            .filter(|d| d.level == "error")
+            .filter_map(|d| d.code.as_deref())
+            .filter(|code| normalize_error_code(code).is_some())
+            .collect();
+        codes.sort_unstable();
+        codes.dedup();
+
+        let mut explanations = serde_json::Map::new();
+        for code in codes {
+            if let Some(explanation) = explain_code(code, params, ctx).await? {
+                explanations.insert(code.to_string(), json!(explanation));
+            }
         }
-    } else {
-        return Ok(json!({
-            "content": [{
-                "type": "text",
-                "text": format!("Pre-build check failed:\n{}{}", stdout, stderr)
-            }]
-        }));
+        for (code, explanation) in &explanations {
+            append_text(
+                &mut result,
+                &format!(
+                    "\n\n## {code}\n\n{}",
+                    explanation.as_str().unwrap_or_default().trim_end()
+                ),
+            );
+        }
+        result["structuredContent"]["explanations"] = Value::Object(explanations);
+    }
+
+    Ok(result)
+}
+
+/// Accept `E0502`, `e0502` or `0502` and return `E0502`.
+fn normalize_error_code(code: &str) -> Option<String> {
+    let code = code.trim();
+    let digits = code.strip_prefix(['E', 'e']).unwrap_or(code);
+    (digits.len() == 4 && digits.chars().all(|c| c.is_ascii_digit())).then(|| format!("E{digits}"))
+}
+
+/// The long explanation `rustc --explain` prints for an error code, or
+/// `None` when rustc does not know the code.
+async fn explain_code(
+    code: &str,
+    params: &CargoToolParams,
+    ctx: &ToolContext,
+) -> Result<Option<String>> {
+    let mut cmd = Command::new("rustc");
+    cmd.args(["--explain", code]);
+    if let Some(working_dir) = &params.working_directory {
+        cmd.current_dir(working_dir);
+    }
+    let output = run_command(cmd, ctx)
+        .await
+        .context("Failed to execute rustc --explain")?;
+    Ok(output.success().then_some(output.stdout))
+}
+
+async fn handle_explain_error(params: &CargoToolParams, ctx: &ToolContext) -> Result<Value> {
+    let requested = params.code.as_deref().unwrap_or_default();
+    let Some(code) = normalize_error_code(requested) else {
+        return Ok(structured_result(
+            format!("{requested:?} is not an error code; expected something like E0502"),
+            false,
+            json!({ "success": false }),
+        ));
     };
 
-    Ok(json!({
-        "content": [{
-            "type": "text",
-            "text": result
-        }]
-    }))
+    match explain_code(&code, params, ctx).await? {
+        Some(explanation) => Ok(structured_result(
+            explanation.clone(),
+            true,
+            json!({
+                "success": true,
+                "code": code,
+                "explanation": explanation,
+            }),
+        )),
+        None => Ok(structured_result(
+            format!("rustc has no explanation for {code}"),
+            false,
+            json!({ "success": false, "code": code }),
+        )),
+    }
 }
 
-pub fn execute_cargo_command(subcommand: &str, params: &CargoToolParams) -> Result<String> {
+/// Build the `cargo <subcommand>` invocation described by `params`.
+pub fn cargo_command(subcommand: &str, params: &CargoToolParams) -> Command {
     let mut cmd = Command::new("cargo");
     cmd.arg(subcommand);
 
@@ -411,28 +1732,11 @@ pub fn execute_cargo_command(subcommand: &str, params: &CargoToolParams) -> Resu
                 }
             }
 
-            if subcommand == "test" {
-                if params.nocapture.unwrap_or(false) {
-                    cmd.arg("--nocapture");
-                }
-                if params.ignored.unwrap_or(false) {
-                    cmd.arg("--ignored");
-                }
-                if params.include_ignored.unwrap_or(false) {
-                    cmd.arg("--include-ignored");
-                }
-                if params.exact.unwrap_or(false) {
-                    cmd.arg("--exact");
-                }
-                if let Some(test_threads) = params.test_threads {
-                    cmd.args(["--test-threads", &test_threads.to_string()]);
-                }
-            }
+            // Test harness options such as `exact` or `test_threads` only
+            // work after `--`; callers that support them add them there,
+            // as `cargo_test_command` does.
 
             if subcommand == "doc" {
-                if params.open.unwrap_or(false) {
-                    cmd.arg("--open");
-                }
                 if params.no_deps.unwrap_or(false) {
                     cmd.arg("--no-deps");
                 }
@@ -466,11 +1770,18 @@ pub fn execute_cargo_command(subcommand: &str, params: &CargoToolParams) -> Resu
             if let Some(ref target) = params.target {
                 cmd.args(["--target", target]);
             }
+            if let Some(ref args) = params.args {
+                cmd.arg("--");
+                cmd.args(args);
+            }
         }
         "fmt" => {
             if let Some(ref package) = params.package {
                 cmd.args(["--package", package]);
             }
+            if params.check.unwrap_or(false) {
+                cmd.args(["--", "--check", "--color", "never"]);
+            }
         }
         "add" => {
             if let Some(ref dependency) = params.dependency {
@@ -766,63 +2077,234 @@ pub fn execute_cargo_command(subcommand: &str, params: &CargoToolParams) -> Resu
         _ => {}
     }
 
-    let output = cmd
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .output()
-        .context("Failed to execute cargo command")?;
+    cmd
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
+/// Tools backed directly by a cargo subcommand of the same name.
+const CARGO_SUBCOMMAND_TOOLS: [&str; 11] = [
+    "build",
+    "run",
+    "fmt",
+    "doc",
+    "update",
+    "tree",
+    "new",
+    "init",
+    "metadata",
+    "install",
+    "uninstall",
+];
+
+async fn handle_cargo_subcommand(
+    subcommand: &str,
+    params: &CargoToolParams,
+    ctx: &ToolContext,
+) -> Result<Value> {
+    let output = run_command(cargo_command(subcommand, params), ctx)
+        .await
+        .with_context(|| format!("Failed to execute cargo {subcommand}"))?;
+
+    if subcommand == "build"
+        && !output.timed_out
+        && params
+            .message_format
+            .as_deref()
+            .is_some_and(|format| format.starts_with("json"))
+    {
+        let diagnostics = parse_diagnostics(&output.stdout);
+        return Ok(diagnostics_result("Build", &output, &diagnostics));
+    }
 
-    let combined_output = if stderr.is_empty() {
-        stdout.to_string()
-    } else if stdout.is_empty() {
-        stderr.to_string()
-    } else {
-        format!("STDOUT:\n{stdout}\n\nSTDERR:\n{stderr}")
-    };
+    if !output.success() {
+        return Ok(failure_result(&format!("cargo {subcommand}"), &output, ctx));
+    }
 
-    if !output.status.success() {
-        return Err(anyhow::anyhow!(
-            "Cargo command failed with exit code {}: {}",
-            output.status.code().unwrap_or(-1),
-            combined_output
-        ));
+    let mut result = output.combined();
+    if result.trim().is_empty() {
+        result = format!("cargo {subcommand} completed successfully");
     }
 
-    Ok(combined_output)
+    Ok(command_result(result, &output, json!({})))
 }
 
-pub fn handle_tool_call(tool_name: &str, params: Value) -> Result<Value> {
+pub async fn handle_tool_call(
+    tool_name: &str,
+    params: Value,
+    config: &ServerConfig,
+    state: &ToolState,
+    cancellation: CancellationToken,
+    progress: Option<ProgressReporter>,
+) -> Result<Value, McpError> {
     let start = Instant::now();
 
-    let cargo_params: CargoToolParams =
-        serde_json::from_value(params).context("Failed to parse tool parameters")?;
+    let cargo_params: CargoToolParams = serde_json::from_value(params)
+        .map_err(|e| McpError::invalid_params(format!("Invalid tool arguments: {e}")))?;
+    let ctx = ToolContext::new(config, &cargo_params, cancellation, progress);
 
     let mut result = match tool_name {
-        "compile" => handle_pre_build(&cargo_params),
-        "lint" => handle_lint(&cargo_params),
-        "test" => handle_test(&cargo_params),
-        "clean" => handle_clean(&cargo_params),
-        "search_crates" => handle_search_crates(&cargo_params),
-        "crate_info" => handle_crate_info(&cargo_params),
-        "add_crate" => handle_add_crate(&cargo_params),
-        "remove_crate" => handle_remove_crate(&cargo_params),
-        _ => Err(anyhow::anyhow!("Unknown tool: {}", tool_name)),
-    }?;
-
-    // Update the execution time in the result
-    if let Some(content) = result.get_mut("content")
-        && let Some(content_array) = content.as_array_mut()
-        && let Some(first_item) = content_array.get_mut(0)
-        && let Some(obj) = first_item.as_object_mut()
-    {
-        obj.insert(
-            "execution_time_ms".to_string(),
-            json!(start.elapsed().as_millis()),
-        );
+        "compile" => handle_pre_build(&cargo_params, &ctx, state).await,
+        "lint" => handle_lint(&cargo_params, &ctx, state).await,
+        "test" => handle_test(&cargo_params, &ctx, state).await,
+        "list_tests" => handle_list_tests(&cargo_params, &ctx).await,
+        "detect_flaky" => handle_detect_flaky(&cargo_params, &ctx).await,
+        "coverage" => handle_coverage(&cargo_params, &ctx).await,
+        "bench" => handle_bench(&cargo_params, &ctx).await,
+        "apply_fixes" => handle_apply_fixes(&cargo_params, state).await,
+        "explain_error" => handle_explain_error(&cargo_params, &ctx).await,
+        "workspace_info" => handle_workspace_info(&cargo_params, &ctx).await,
+        "dependency_graph" => handle_dependency_graph(&cargo_params, &ctx).await,
+        "duplicate_dependencies" => handle_duplicate_dependencies(&cargo_params, &ctx).await,
+        "outdated" => handle_outdated(&cargo_params, &ctx).await,
+        "audit" => handle_audit(&cargo_params, &ctx).await,
+        "clean" => handle_clean(&cargo_params, &ctx).await,
+        "search_crates" => handle_search_crates(&cargo_params, &ctx).await,
+        "crate_info" => handle_crate_info(&cargo_params, &ctx).await,
+        "add_crate" => handle_add_crate(&cargo_params, &ctx).await,
+        "remove_crate" => handle_remove_crate(&cargo_params, &ctx).await,
+        name if CARGO_SUBCOMMAND_TOOLS.contains(&name) => {
+            handle_cargo_subcommand(name, &cargo_params, &ctx).await
+        }
+        _ => {
+            return Err(McpError::invalid_params(format!(
+                "Unknown tool: {tool_name}"
+            )));
+        }
     }
+    // Only bad arguments and unknown tools are protocol errors; anything
+    // that goes wrong while running the tool is reported in the result.
+    .unwrap_or_else(|e| {
+        structured_result(
+            format!("{tool_name} failed: {e:#}"),
+            false,
+            json!({ "success": false, "error": format!("{e:#}") }),
+        )
+    });
+
+    finalize_result(&mut result, start.elapsed().as_millis());
 
     Ok(result)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(arguments: Value) -> CargoToolParams {
+        serde_json::from_value(arguments).unwrap()
+    }
+
+    fn args(cmd: &Command) -> Vec<String> {
+        cmd.as_std()
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn selects_requested_enclosing_or_default_members() {
+        let metadata: Metadata =
+            serde_json::from_str(include_str!("testdata/metadata_workspace.json")).unwrap();
+        let selected = |arguments: Value| -> Vec<String> {
+            selected_members(&metadata, &params(arguments))
+                .into_iter()
+                .map(|member| member.name.clone())
+                .collect()
+        };
+
+        assert_eq!(
+            selected(json!({"package": "macros", "working_directory": "/tmp/ws/app"})),
+            ["macros"]
+        );
+        assert!(selected(json!({"package": "missing"})).is_empty());
+        assert_eq!(
+            selected(json!({"working_directory": "/tmp/ws/core/src/nested"})),
+            ["core-utils"]
+        );
+        assert_eq!(selected(json!({"working_directory": "/tmp/ws"})), ["app"]);
+        assert_eq!(selected(json!({"working_directory": "/tmp"})), ["app"]);
+    }
+
+    #[test]
+    fn falls_back_to_cargo_test_without_nextest() {
+        assert_eq!(
+            select_runner(TestRunner::Nextest, false, true),
+            (TestRunner::Nextest, None)
+        );
+        assert_eq!(
+            select_runner(TestRunner::Nextest, false, false),
+            (
+                TestRunner::Cargo,
+                Some("cargo-nextest is not installed; ran cargo test instead.")
+            )
+        );
+        assert_eq!(
+            select_runner(TestRunner::Nextest, true, true),
+            (
+                TestRunner::Cargo,
+                Some("cargo-nextest does not run doctests; ran cargo test instead.")
+            )
+        );
+        assert_eq!(
+            select_runner(TestRunner::Cargo, false, true),
+            (TestRunner::Cargo, None)
+        );
+    }
+
+    #[test]
+    fn fallback_keeps_libtest_json_output() {
+        // The cargo fallback of a nextest run is always structured, so the
+        // result has the same shape.
+        let cmd = cargo_test_command(&params(json!({"test_name": "adds"})), None, true, false);
+        assert_eq!(
+            args(&cmd),
+            [
+                "test",
+                "adds",
+                "--",
+                "-Z",
+                "unstable-options",
+                "--format",
+                "json",
+                "--report-time"
+            ]
+        );
+        let rerun = ["tests::fails".to_string()];
+        let cmd = cargo_test_command(&params(json!({})), Some(&rerun), true, true);
+        assert_eq!(
+            args(&cmd)[..5],
+            ["test", "--no-fail-fast", "--", "--exact", "tests::fails"]
+        );
+    }
+
+    #[test]
+    fn writes_exact_nextest_matches_as_filtersets() {
+        let cmd = nextest_command(
+            &params(json!({"package": "lt", "no_fail_fast": true})),
+            None,
+        );
+        assert_eq!(
+            args(&cmd),
+            [
+                "nextest",
+                "run",
+                "--message-format",
+                "libtest-json",
+                "--no-fail-fast",
+                "-p",
+                "lt"
+            ]
+        );
+
+        let cmd = nextest_command(&params(json!({"test_name": "adds"})), None);
+        assert_eq!(args(&cmd)[4..], ["adds"]);
+        let cmd = nextest_command(&params(json!({"test_name": "adds", "exact": true})), None);
+        assert_eq!(args(&cmd)[4..], ["-E", "test(=adds)"]);
+
+        let rerun = ["tests::fails".to_string(), "api_works".to_string()];
+        let cmd = nextest_command(&params(json!({"test_name": "adds"})), Some(&rerun));
+        assert_eq!(
+            args(&cmd)[4..],
+            ["-E", "test(=tests::fails) | test(=api_works)"]
+        );
+    }
+}
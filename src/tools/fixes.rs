@@ -0,0 +1,411 @@
+use serde::Serialize;
+use similar::TextDiff;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::diagnostics::Diagnostic;
+
+/// A requested diagnostic that could not be fixed.
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedFix {
+    pub id: String,
+    pub reason: String,
+}
+
+/// New contents of one source file.
+#[derive(Debug, Clone, Serialize)]
+pub struct FileFix {
+    pub file: String,
+    pub diff: String,
+    #[serde(skip)]
+    pub path: PathBuf,
+    #[serde(skip)]
+    pub contents: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct FixPlan {
+    pub applied: Vec<String>,
+    pub skipped: Vec<SkippedFix>,
+    pub files: Vec<FileFix>,
+}
+
+impl FixPlan {
+    /// All file diffs, concatenated.
+    pub fn diff(&self) -> String {
+        self.files.iter().map(|file| file.diff.as_str()).collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Edit {
+    start: usize,
+    end: usize,
+    replacement: String,
+}
+
+impl Edit {
+    fn conflicts_with(&self, other: &Edit) -> bool {
+        if self == other {
+            return false;
+        }
+        // Two insertions at the same point have no defined order.
+        if self.start == self.end && other.start == other.end {
+            return self.start == other.start;
+        }
+        // Also catches an insertion strictly inside a replaced range.
+        self.start < other.end && other.start < self.end
+    }
+}
+
+/// Work out the edits for the machine-applicable suggestions of the given
+/// diagnostics. Span paths are relative to `base`, the directory cargo ran
+/// rustc in. A diagnostic is applied completely or not at all: it is
+/// skipped when it has no machine-applicable suggestion, when its edits
+/// overlap each other or an edit already accepted, or when a file it
+/// touches changed after `recorded_at`.
+pub fn plan_fixes(base: &Path, diagnostics: &[&Diagnostic], recorded_at: SystemTime) -> FixPlan {
+    let mut plan = FixPlan::default();
+    let mut originals: BTreeMap<String, String> = BTreeMap::new();
+    let mut accepted: Vec<(String, Vec<(String, Edit)>)> = Vec::new();
+
+    'diagnostics: for diagnostic in diagnostics {
+        let id = diagnostic.id.clone().unwrap_or_default();
+        let mut skip = |reason: String| {
+            plan.skipped.push(SkippedFix {
+                id: id.clone(),
+                reason,
+            })
+        };
+
+        let suggestions: Vec<_> = diagnostic
+            .suggestions
+            .iter()
+            .filter(|s| s.applicability.as_deref() == Some("MachineApplicable"))
+            .collect();
+        if suggestions.is_empty() {
+            skip("no machine-applicable suggestion".to_string());
+            continue;
+        }
+
+        let mut edits: Vec<(String, Edit)> = Vec::new();
+        for suggestion in suggestions {
+            let file = suggestion.location.file.clone();
+            if !originals.contains_key(&file) {
+                let path = base.join(&file);
+                let changed = std::fs::metadata(&path)
+                    .and_then(|metadata| metadata.modified())
+                    .map_or(true, |modified| modified > recorded_at);
+                if changed {
+                    skip(format!(
+                        "{file} changed after the diagnostics were produced; run the check again"
+                    ));
+                    continue 'diagnostics;
+                }
+                let Ok(contents) = std::fs::read_to_string(&path) else {
+                    skip(format!("cannot read {file}"));
+                    continue 'diagnostics;
+                };
+                originals.insert(file.clone(), contents);
+            }
+
+            let contents = &originals[&file];
+            let (start, end) = (suggestion.byte_start as usize, suggestion.byte_end as usize);
+            if start > end
+                || end > contents.len()
+                || !contents.is_char_boundary(start)
+                || !contents.is_char_boundary(end)
+            {
+                skip(format!("suggestion span is outside {file}"));
+                continue 'diagnostics;
+            }
+            let edit = (
+                file,
+                Edit {
+                    start,
+                    end,
+                    replacement: suggestion.replacement.clone(),
+                },
+            );
+            if !edits.contains(&edit) {
+                edits.push(edit);
+            }
+        }
+
+        // Alternative suggestions from different `help` children end up in
+        // the same list and may rewrite the same code.
+        if overlapping(&edits, &edits) {
+            skip("its suggestions overlap each other".to_string());
+            continue;
+        }
+        if accepted
+            .iter()
+            .any(|(_, accepted_edits)| overlapping(&edits, accepted_edits))
+        {
+            skip("overlaps a fix that was already applied".to_string());
+            continue;
+        }
+        accepted.push((id, edits));
+    }
+
+    // Edits are checked against the original contents above, so applying
+    // them back to front cannot fail; should it anyway, the diagnostics
+    // touching that file are dropped rather than writing a broken file.
+    loop {
+        let mut by_file: BTreeMap<&str, Vec<&Edit>> = BTreeMap::new();
+        for (file, edit) in accepted.iter().flat_map(|(_, edits)| edits) {
+            let file_edits = by_file.entry(file).or_default();
+            if !file_edits.contains(&edit) {
+                file_edits.push(edit);
+            }
+        }
+
+        let mut files = Vec::new();
+        let mut failed = None;
+        for (file, edits) in by_file {
+            let original = &originals[file];
+            match apply_edits(original, edits) {
+                Some(contents) => files.push((file.to_string(), contents)),
+                None => {
+                    failed = Some(file.to_string());
+                    break;
+                }
+            }
+        }
+
+        let Some(failed) = failed else {
+            for (file, contents) in files {
+                let original = &originals[&file];
+                let diff = TextDiff::from_lines(original.as_str(), contents.as_str())
+                    .unified_diff()
+                    .header(&format!("a/{file}"), &format!("b/{file}"))
+                    .to_string();
+                plan.files.push(FileFix {
+                    path: base.join(&file),
+                    file,
+                    diff,
+                    contents,
+                });
+            }
+            break;
+        };
+        accepted.retain(|(id, edits)| {
+            let touches = edits.iter().any(|(file, _)| *file == failed);
+            if touches {
+                plan.skipped.push(SkippedFix {
+                    id: id.clone(),
+                    reason: format!("cannot apply the suggestions to {failed}"),
+                });
+            }
+            !touches
+        });
+    }
+
+    plan.applied = accepted.into_iter().map(|(id, _)| id).collect();
+    plan
+}
+
+/// Whether an edit in `edits` conflicts with one in `others` to the same
+/// file. Identical edits do not conflict.
+fn overlapping(edits: &[(String, Edit)], others: &[(String, Edit)]) -> bool {
+    edits.iter().any(|(file, edit)| {
+        others
+            .iter()
+            .any(|(other_file, other)| file == other_file && edit.conflicts_with(other))
+    })
+}
+
+/// Apply non-overlapping edits, last first so earlier offsets stay valid.
+fn apply_edits(original: &str, mut edits: Vec<&Edit>) -> Option<String> {
+    let mut contents = original.to_string();
+    edits.sort_by_key(|edit| std::cmp::Reverse((edit.start, edit.end)));
+    for edit in edits {
+        if edit.end > contents.len()
+            || !contents.is_char_boundary(edit.start)
+            || !contents.is_char_boundary(edit.end)
+        {
+            return None;
+        }
+        contents.replace_range(edit.start..edit.end, &edit.replacement);
+    }
+    Some(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::diagnostics::{SpanLocation, Suggestion};
+    use std::time::Duration;
+
+    const SOURCE: &str = "fn main() {\n    let x = 5;\n    let y = vec![1];\n}\n";
+
+    /// A fresh directory holding `src/main.rs` with `contents`.
+    fn workspace(name: &str, contents: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("cargo-mcp-fixes-{}-{name}", std::process::id()));
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(dir.join("src/main.rs"), contents).unwrap();
+        dir
+    }
+
+    fn suggestion(start: usize, end: usize, replacement: &str) -> Suggestion {
+        Suggestion {
+            location: SpanLocation {
+                file: "src/main.rs".to_string(),
+                line: 1,
+                column: 1,
+                end_line: 1,
+                end_column: 1,
+            },
+            byte_start: start as u32,
+            byte_end: end as u32,
+            replacement: replacement.to_string(),
+            applicability: Some("MachineApplicable".to_string()),
+        }
+    }
+
+    fn diagnostic(id: &str, suggestions: Vec<Suggestion>) -> Diagnostic {
+        Diagnostic {
+            id: Some(id.to_string()),
+            level: "warning".to_string(),
+            code: None,
+            message: String::new(),
+            span: None,
+            labels: Vec::new(),
+            children: Vec::new(),
+            suggestions,
+        }
+    }
+
+    fn offset(needle: &str) -> usize {
+        SOURCE.find(needle).unwrap()
+    }
+
+    fn later() -> SystemTime {
+        SystemTime::now() + Duration::from_secs(60)
+    }
+
+    #[test]
+    fn applies_edits_back_to_front() {
+        let dir = workspace("apply", SOURCE);
+        let x = offset("x =");
+        let vec = offset("vec!");
+        // `let x` becomes `let _x`, `vec![1]` becomes `[1]`, and an
+        // insertion lands before `let y`.
+        let unused = diagnostic("W1", vec![suggestion(x, x + 1, "_x")]);
+        let useless = diagnostic(
+            "W2",
+            vec![
+                suggestion(vec, vec + 4, ""),
+                suggestion(offset("let y"), offset("let y"), "#[allow(unused)] "),
+            ],
+        );
+
+        let plan = plan_fixes(&dir, &[&unused, &useless], later());
+        assert_eq!(plan.applied, ["W1", "W2"]);
+        assert!(plan.skipped.is_empty());
+        assert_eq!(plan.files.len(), 1);
+        assert_eq!(
+            plan.files[0].contents,
+            "fn main() {\n    let _x = 5;\n    #[allow(unused)] let y = [1];\n}\n"
+        );
+        assert!(plan.diff().contains("+    let _x = 5;"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_overlapping_suggestions() {
+        let dir = workspace("overlap", SOURCE);
+        let x = offset("x =");
+        // Two alternative fixes for the same span from different children.
+        let alternatives = diagnostic(
+            "W1",
+            vec![suggestion(x, x + 1, "_x"), suggestion(x, x + 1, "_")],
+        );
+        // Two insertions at one point have no defined order either.
+        let insertions = diagnostic("W2", vec![suggestion(x, x, "a"), suggestion(x, x, "b")]);
+        // The same edit twice is applied once.
+        let repeated = diagnostic(
+            "W3",
+            vec![suggestion(x, x + 1, "_x"), suggestion(x, x + 1, "_x")],
+        );
+        // Overlaps the edit W3 already made.
+        let later_overlap = diagnostic("W4", vec![suggestion(x - 4, x + 1, "let _")]);
+
+        let plan = plan_fixes(
+            &dir,
+            &[&alternatives, &insertions, &repeated, &later_overlap],
+            later(),
+        );
+        assert_eq!(plan.applied, ["W3"]);
+        let skipped: Vec<(&str, &str)> = plan
+            .skipped
+            .iter()
+            .map(|skip| (skip.id.as_str(), skip.reason.as_str()))
+            .collect();
+        assert_eq!(
+            skipped,
+            [
+                ("W1", "its suggestions overlap each other"),
+                ("W2", "its suggestions overlap each other"),
+                ("W4", "overlaps a fix that was already applied"),
+            ]
+        );
+        assert!(plan.files[0].contents.contains("let _x = 5;"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn skips_files_changed_since_the_check() {
+        let dir = workspace("stale", SOURCE);
+        let x = offset("x =");
+        let unused = diagnostic("W1", vec![suggestion(x, x + 1, "_x")]);
+
+        let plan = plan_fixes(&dir, &[&unused], SystemTime::UNIX_EPOCH);
+        assert!(plan.applied.is_empty());
+        assert!(plan.files.is_empty());
+        assert_eq!(
+            plan.skipped[0].reason,
+            "src/main.rs changed after the diagnostics were produced; run the check again"
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn skips_spans_outside_the_file_or_inside_a_character() {
+        let dir = workspace("spans", "// é\n");
+        let past_end = diagnostic("W1", vec![suggestion(10, 12, "")]);
+        let mid_char = diagnostic("W2", vec![suggestion(4, 4, "x")]);
+        let not_applicable = diagnostic(
+            "W3",
+            vec![Suggestion {
+                applicability: Some("MaybeIncorrect".to_string()),
+                ..suggestion(0, 2, "")
+            }],
+        );
+
+        let plan = plan_fixes(&dir, &[&past_end, &mid_char, &not_applicable], later());
+        assert!(plan.applied.is_empty());
+        let reasons: Vec<&str> = plan.skipped.iter().map(|s| s.reason.as_str()).collect();
+        assert_eq!(
+            reasons,
+            [
+                "suggestion span is outside src/main.rs",
+                "suggestion span is outside src/main.rs",
+                "no machine-applicable suggestion",
+            ]
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn apply_edits_refuses_offsets_inside_a_character() {
+        let edit = Edit {
+            start: 4,
+            end: 4,
+            replacement: "x".to_string(),
+        };
+        assert_eq!(apply_edits("// é\n", vec![&edit]), None);
+    }
+}
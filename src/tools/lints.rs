@@ -0,0 +1,435 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::Path;
+
+use crate::types::CargoToolParams;
+
+/// Clippy lint groups accepted by `lint_groups`.
+pub const LINT_GROUPS: &[&str] = &["all", "pedantic", "nursery", "cargo", "restriction"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+    Forbid,
+}
+
+impl LintLevel {
+    fn parse(level: &str) -> Option<Self> {
+        match level {
+            "allow" => Some(Self::Allow),
+            "warn" => Some(Self::Warn),
+            "deny" => Some(Self::Deny),
+            "forbid" => Some(Self::Forbid),
+            _ => None,
+        }
+    }
+
+    fn flag(self) -> &'static str {
+        match self {
+            Self::Allow => "-A",
+            Self::Warn => "-W",
+            Self::Deny => "-D",
+            Self::Forbid => "-F",
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Allow => "allow",
+            Self::Warn => "warn",
+            Self::Deny => "deny",
+            Self::Forbid => "forbid",
+        }
+    }
+}
+
+/// Where a lint level came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LintSource {
+    /// A `[lints]` table, either the package's or the inherited
+    /// `[workspace.lints]`.
+    Manifest,
+    /// Flags the `lint` tool passed to clippy.
+    CommandLine,
+}
+
+impl LintSource {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Manifest => "manifest",
+            Self::CommandLine => "command line",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LintSetting {
+    /// Lint or lint group, e.g. `clippy::pedantic` or `unsafe_code`.
+    pub lint: String,
+    pub level: LintLevel,
+    pub source: LintSource,
+}
+
+impl std::fmt::Display for LintSetting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} = {} ({})",
+            self.lint,
+            self.level.as_str(),
+            self.source.as_str()
+        )
+    }
+}
+
+/// Lint levels requested through the tool parameters, in the order they
+/// are passed to clippy. Later flags override earlier ones, so explicit
+/// `allow`/`warn`/`deny` entries win over the groups.
+#[derive(Debug, Default)]
+pub struct LintPolicy {
+    settings: Vec<LintSetting>,
+}
+
+impl LintPolicy {
+    /// Without any lint options this keeps the original behavior: the
+    /// pedantic group denied, or warned with `warn_only`. The error is a
+    /// message for the caller.
+    pub fn from_params(params: &CargoToolParams) -> Result<Self, String> {
+        let lists = [
+            (&params.allow, LintLevel::Allow),
+            (&params.warn, LintLevel::Warn),
+            (&params.deny, LintLevel::Deny),
+        ];
+
+        if params.workspace_lints_only.unwrap_or(false) {
+            let overridden = params.lint_groups.is_some()
+                || params.warn_only.unwrap_or(false)
+                || params.ignore_docs.unwrap_or(false)
+                || lists.iter().any(|(lints, _)| lints.is_some());
+            if overridden {
+                return Err(
+                    "workspace_lints_only cannot be combined with other lint options".to_string(),
+                );
+            }
+            return Ok(Self::default());
+        }
+
+        let mut policy = Self::default();
+        let group_level = if params.warn_only.unwrap_or(false) {
+            LintLevel::Warn
+        } else {
+            LintLevel::Deny
+        };
+        let default_groups = ["pedantic".to_string()];
+        for group in params.lint_groups.as_deref().unwrap_or(&default_groups) {
+            if !LINT_GROUPS.contains(&group.as_str()) {
+                return Err(format!(
+                    "Unknown lint group {group:?}; expected one of {}",
+                    LINT_GROUPS.join(", ")
+                ));
+            }
+            policy.push(format!("clippy::{group}"), group_level);
+        }
+
+        if params.ignore_docs.unwrap_or(false) {
+            policy.push("clippy::missing_docs".to_string(), LintLevel::Allow);
+        }
+
+        for (lints, level) in lists {
+            for lint in lints.iter().flatten() {
+                let valid = !lint.is_empty()
+                    && lint
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':');
+                if !valid {
+                    return Err(format!("Invalid lint name {lint:?}"));
+                }
+                policy.push(lint.clone(), level);
+            }
+        }
+
+        Ok(policy)
+    }
+
+    fn push(&mut self, lint: String, level: LintLevel) {
+        self.settings.push(LintSetting {
+            lint,
+            level,
+            source: LintSource::CommandLine,
+        });
+    }
+
+    /// Arguments for clippy, after `--`.
+    pub fn clippy_args(&self) -> Vec<String> {
+        self.settings
+            .iter()
+            .map(|setting| format!("{}{}", setting.level.flag(), setting.lint))
+            .collect()
+    }
+
+    pub fn settings(&self) -> &[LintSetting] {
+        &self.settings
+    }
+}
+
+fn read_manifest(path: &Path) -> Result<toml::Table> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    toml::from_str(&contents).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Lint levels a package's manifest configures, following
+/// `[lints] workspace = true` to the workspace manifest. They are ordered
+/// the way cargo passes them to rustc: by priority, then by name.
+pub fn manifest_lints(manifest: &Path, workspace_manifest: &Path) -> Result<Vec<LintSetting>> {
+    let package = read_manifest(manifest)?;
+    let inherits = package
+        .get("lints")
+        .and_then(|lints| lints.get("workspace"))
+        .and_then(toml::Value::as_bool)
+        .unwrap_or(false);
+    let lints = if inherits {
+        read_manifest(workspace_manifest)?
+            .get("workspace")
+            .and_then(|workspace| workspace.get("lints"))
+            .cloned()
+    } else {
+        package.get("lints").cloned()
+    };
+    let Some(toml::Value::Table(lints)) = lints else {
+        return Ok(Vec::new());
+    };
+
+    let mut entries = Vec::new();
+    for (tool, tool_lints) in &lints {
+        let Some(tool_lints) = tool_lints.as_table() else {
+            continue;
+        };
+        for (name, spec) in tool_lints {
+            let (level, priority) = match spec {
+                toml::Value::String(level) => (level.as_str(), 0),
+                toml::Value::Table(spec) => (
+                    spec.get("level")
+                        .and_then(toml::Value::as_str)
+                        .unwrap_or_default(),
+                    spec.get("priority")
+                        .and_then(toml::Value::as_integer)
+                        .unwrap_or(0),
+                ),
+                _ => continue,
+            };
+            let Some(level) = LintLevel::parse(level) else {
+                continue;
+            };
+            let lint = if tool == "rust" {
+                name.clone()
+            } else {
+                format!("{tool}::{name}")
+            };
+            entries.push((priority, lint, level));
+        }
+    }
+    entries.sort_by(|a, b| (a.0, &a.1).cmp(&(b.0, &b.1)));
+
+    Ok(entries
+        .into_iter()
+        .map(|(_, lint, level)| LintSetting {
+            lint,
+            level,
+            source: LintSource::Manifest,
+        })
+        .collect())
+}
+
+/// Combine manifest and command-line levels the way rustc applies them:
+/// command-line flags come last and override earlier levels for the same
+/// lint, except where the earlier level is `forbid`. The result lists the
+/// surviving settings in the order they are applied.
+pub fn effective_lints(manifest: Vec<LintSetting>, policy: &LintPolicy) -> Vec<LintSetting> {
+    let mut effective: Vec<LintSetting> = Vec::new();
+    for setting in manifest
+        .into_iter()
+        .chain(policy.settings().iter().cloned())
+    {
+        if let Some(position) = effective.iter().position(|s| s.lint == setting.lint) {
+            if effective[position].level == LintLevel::Forbid {
+                continue;
+            }
+            effective.remove(position);
+        }
+        effective.push(setting);
+    }
+    effective
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn policy_for(arguments: serde_json::Value) -> Result<LintPolicy, String> {
+        LintPolicy::from_params(&serde_json::from_value(arguments).unwrap())
+    }
+
+    fn setting(lint: &str, level: LintLevel, source: LintSource) -> LintSetting {
+        LintSetting {
+            lint: lint.to_string(),
+            level,
+            source,
+        }
+    }
+
+    fn summary(settings: &[LintSetting]) -> Vec<String> {
+        settings.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn denies_pedantic_by_default() {
+        assert_eq!(
+            policy_for(json!({})).unwrap().clippy_args(),
+            ["-Dclippy::pedantic"]
+        );
+        assert_eq!(
+            policy_for(json!({"warn_only": true, "ignore_docs": true}))
+                .unwrap()
+                .clippy_args(),
+            ["-Wclippy::pedantic", "-Aclippy::missing_docs"]
+        );
+    }
+
+    #[test]
+    fn passes_explicit_levels_after_the_groups() {
+        let policy = policy_for(json!({
+            "lint_groups": ["nursery", "cargo"],
+            "deny": ["unsafe_code"],
+            "allow": ["clippy::module_name_repetitions"],
+            "warn": ["clippy::unwrap_used"],
+        }))
+        .unwrap();
+        assert_eq!(
+            policy.clippy_args(),
+            [
+                "-Dclippy::nursery",
+                "-Dclippy::cargo",
+                "-Aclippy::module_name_repetitions",
+                "-Wclippy::unwrap_used",
+                "-Dunsafe_code",
+            ]
+        );
+        assert!(
+            policy
+                .settings()
+                .iter()
+                .all(|setting| setting.source == LintSource::CommandLine)
+        );
+        assert!(
+            policy_for(json!({"lint_groups": []}))
+                .unwrap()
+                .settings()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_lint_options() {
+        assert_eq!(
+            policy_for(json!({"lint_groups": ["style"]})).unwrap_err(),
+            "Unknown lint group \"style\"; expected one of all, pedantic, nursery, cargo, restriction"
+        );
+        assert_eq!(
+            policy_for(json!({"allow": ["clippy::foo -Dwarnings"]})).unwrap_err(),
+            "Invalid lint name \"clippy::foo -Dwarnings\""
+        );
+        assert!(policy_for(json!({"deny": [""]})).is_err());
+        assert_eq!(
+            policy_for(json!({"workspace_lints_only": true, "warn_only": true})).unwrap_err(),
+            "workspace_lints_only cannot be combined with other lint options"
+        );
+        assert!(
+            policy_for(json!({"workspace_lints_only": true}))
+                .unwrap()
+                .clippy_args()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn reads_package_and_inherited_workspace_lints() {
+        let dir = std::env::temp_dir().join(format!("cargo-mcp-lints-{}", std::process::id()));
+        let member = dir.join("member");
+        std::fs::create_dir_all(&member).unwrap();
+        let workspace_manifest = dir.join("Cargo.toml");
+        std::fs::write(
+            &workspace_manifest,
+            r#"
+[workspace]
+members = ["member"]
+
+[workspace.lints.rust]
+unsafe_code = "forbid"
+
+[workspace.lints.clippy]
+pedantic = { level = "warn", priority = -1 }
+module_name_repetitions = "allow"
+"#,
+        )
+        .unwrap();
+
+        let manifest = member.join("Cargo.toml");
+        std::fs::write(
+            &manifest,
+            "[package]\nname = \"member\"\n\n[lints]\nworkspace = true\n",
+        )
+        .unwrap();
+        assert_eq!(
+            summary(&manifest_lints(&manifest, &workspace_manifest).unwrap()),
+            [
+                "clippy::pedantic = warn (manifest)",
+                "clippy::module_name_repetitions = allow (manifest)",
+                "unsafe_code = forbid (manifest)",
+            ]
+        );
+
+        std::fs::write(
+            &manifest,
+            "[package]\nname = \"member\"\n\n[lints.rust]\nmissing_docs = \"warn\"\nbogus = \"sometimes\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            summary(&manifest_lints(&manifest, &workspace_manifest).unwrap()),
+            ["missing_docs = warn (manifest)"]
+        );
+
+        std::fs::write(&manifest, "[package]\nname = \"member\"\n").unwrap();
+        assert!(
+            manifest_lints(&manifest, &workspace_manifest)
+                .unwrap()
+                .is_empty()
+        );
+        assert!(manifest_lints(&dir.join("missing.toml"), &workspace_manifest).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn command_line_levels_override_all_but_forbid() {
+        let manifest = vec![
+            setting("clippy::pedantic", LintLevel::Warn, LintSource::Manifest),
+            setting("unsafe_code", LintLevel::Forbid, LintSource::Manifest),
+            setting("missing_docs", LintLevel::Warn, LintSource::Manifest),
+        ];
+        let policy = policy_for(json!({"allow": ["unsafe_code", "clippy::unwrap_used"]})).unwrap();
+        assert_eq!(
+            summary(&effective_lints(manifest, &policy)),
+            [
+                "unsafe_code = forbid (manifest)",
+                "missing_docs = warn (manifest)",
+                "clippy::pedantic = deny (command line)",
+                "clippy::unwrap_used = allow (command line)",
+            ]
+        );
+    }
+}
@@ -0,0 +1,454 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// The parts of `cargo metadata --format-version 1` the tools use.
+#[derive(Debug, Deserialize)]
+pub struct Metadata {
+    pub packages: Vec<Package>,
+    pub workspace_members: Vec<String>,
+    /// Missing on cargo releases before 1.71.
+    #[serde(default)]
+    pub workspace_default_members: Option<Vec<String>>,
+    pub workspace_root: PathBuf,
+    pub target_directory: PathBuf,
+    /// Only present when dependencies were resolved, i.e. without
+    /// `--no-deps`.
+    #[serde(default)]
+    pub resolve: Option<Resolve>,
+}
+
+impl Metadata {
+    /// Workspace members, in the order cargo lists them.
+    pub fn members(&self) -> impl Iterator<Item = &Package> {
+        self.packages
+            .iter()
+            .filter(|package| self.workspace_members.contains(&package.id))
+    }
+
+    /// Whether cargo selects this member when run from the workspace root
+    /// without `-p`. Without default member information every member is.
+    pub fn is_default_member(&self, package: &Package) -> bool {
+        self.workspace_default_members
+            .as_ref()
+            .is_none_or(|ids| ids.contains(&package.id))
+    }
+
+    /// `path` relative to the workspace root, for display.
+    pub fn relative<'a>(&self, path: &'a Path) -> &'a Path {
+        path.strip_prefix(&self.workspace_root).unwrap_or(path)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Package {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    /// `None` for path dependencies and workspace members.
+    #[serde(default)]
+    pub source: Option<String>,
+    pub manifest_path: PathBuf,
+    #[serde(default)]
+    pub edition: String,
+    #[serde(default)]
+    pub rust_version: Option<String>,
+    #[serde(default)]
+    pub targets: Vec<Target>,
+    #[serde(default)]
+    pub features: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    pub dependencies: Vec<Dependency>,
+}
+
+impl Package {
+    pub fn is_proc_macro(&self) -> bool {
+        self.targets
+            .iter()
+            .any(|target| target.kind.iter().any(|kind| kind == "proc-macro"))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Target {
+    pub name: String,
+    pub kind: Vec<String>,
+    #[serde(default)]
+    pub crate_types: Vec<String>,
+    pub src_path: PathBuf,
+    #[serde(default, rename = "required-features")]
+    pub required_features: Vec<String>,
+}
+
+impl Target {
+    /// `lib` for every library flavor (rlib, cdylib, proc-macro, ...),
+    /// otherwise cargo's kind: bin, example, test, bench or custom-build.
+    pub fn kind(&self) -> &str {
+        const LIBRARY_KINDS: &[&str] =
+            &["lib", "rlib", "dylib", "cdylib", "staticlib", "proc-macro"];
+        match self.kind.first() {
+            Some(kind) if LIBRARY_KINDS.contains(&kind.as_str()) => "lib",
+            Some(kind) => kind,
+            None => "unknown",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Dependency {
+    pub name: String,
+    pub req: String,
+    /// `None` for normal dependencies, otherwise `dev` or `build`. Left out
+    /// of summaries, which group dependencies by kind.
+    #[serde(default, skip_serializing)]
+    pub kind: Option<String>,
+    #[serde(default)]
+    pub optional: bool,
+    #[serde(default = "default_true")]
+    pub uses_default_features: bool,
+    #[serde(default)]
+    pub features: Vec<String>,
+    /// Platform the dependency is limited to, e.g. `cfg(windows)`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rename: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+}
+
+/// The resolved dependency graph.
+#[derive(Debug, Deserialize)]
+pub struct Resolve {
+    pub nodes: Vec<ResolveNode>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResolveNode {
+    pub id: String,
+    #[serde(default)]
+    pub deps: Vec<NodeDep>,
+    /// Features enabled on this package.
+    #[serde(default)]
+    pub features: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NodeDep {
+    /// Name the dependent refers to the dependency by, with renames applied
+    /// and dashes replaced by underscores.
+    pub name: String,
+    pub pkg: String,
+    #[serde(default)]
+    pub dep_kinds: Vec<DepKindInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DepKindInfo {
+    /// `None` for normal dependencies, otherwise `dev` or `build`.
+    #[serde(default, serialize_with = "serialize_kind")]
+    pub kind: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+}
+
+impl DepKindInfo {
+    pub fn kind(&self) -> &str {
+        self.kind.as_deref().unwrap_or("normal")
+    }
+}
+
+fn serialize_kind<S: serde::Serializer>(
+    kind: &Option<String>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(kind.as_deref().unwrap_or("normal"))
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Dependency {
+    pub fn kind(&self) -> &str {
+        self.kind.as_deref().unwrap_or("normal")
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TargetSummary {
+    pub name: String,
+    pub kind: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub crate_types: Vec<String>,
+    pub src_path: PathBuf,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub required_features: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PackageSummary {
+    pub name: String,
+    pub version: String,
+    pub manifest_path: PathBuf,
+    pub edition: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rust_version: Option<String>,
+    pub is_default_member: bool,
+    pub targets: Vec<TargetSummary>,
+    /// Each feature and the features or dependencies it enables.
+    pub features: BTreeMap<String, Vec<String>>,
+    /// Declared dependencies grouped by kind: normal, dev and build.
+    pub dependencies: BTreeMap<String, Vec<Dependency>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkspaceSummary {
+    pub workspace_root: PathBuf,
+    pub target_directory: PathBuf,
+    pub members: Vec<PackageSummary>,
+}
+
+/// Summarize the workspace members, with paths relative to the workspace
+/// root.
+pub fn summarize_workspace(metadata: &Metadata) -> WorkspaceSummary {
+    let members = metadata
+        .members()
+        .map(|package| {
+            let targets = package
+                .targets
+                .iter()
+                .map(|target| {
+                    let kind = target.kind().to_string();
+                    TargetSummary {
+                        crate_types: if kind == "lib" {
+                            target.crate_types.clone()
+                        } else {
+                            Vec::new()
+                        },
+                        kind,
+                        name: target.name.clone(),
+                        src_path: metadata.relative(&target.src_path).to_path_buf(),
+                        required_features: target.required_features.clone(),
+                    }
+                })
+                .collect();
+
+            let mut dependencies: BTreeMap<String, Vec<Dependency>> = BTreeMap::new();
+            for dependency in &package.dependencies {
+                let mut dependency = dependency.clone();
+                dependency.path = dependency
+                    .path
+                    .map(|path| metadata.relative(&path).to_path_buf());
+                dependencies
+                    .entry(dependency.kind().to_string())
+                    .or_default()
+                    .push(dependency);
+            }
+
+            PackageSummary {
+                name: package.name.clone(),
+                version: package.version.clone(),
+                manifest_path: metadata.relative(&package.manifest_path).to_path_buf(),
+                edition: package.edition.clone(),
+                rust_version: package.rust_version.clone(),
+                is_default_member: metadata.is_default_member(package),
+                targets,
+                features: package.features.clone(),
+                dependencies,
+            }
+        })
+        .collect();
+
+    WorkspaceSummary {
+        workspace_root: metadata.workspace_root.clone(),
+        target_directory: metadata.target_directory.clone(),
+        members,
+    }
+}
+
+impl WorkspaceSummary {
+    /// A compact outline of the members for the text content.
+    pub fn outline(&self) -> String {
+        let mut text = format!(
+            "Workspace at {} with {} member{}",
+            self.workspace_root.display(),
+            self.members.len(),
+            if self.members.len() == 1 { "" } else { "s" }
+        );
+        for member in &self.members {
+            text.push_str(&format!(
+                "\n\n{} {} (edition {}",
+                member.name, member.version, member.edition
+            ));
+            if let Some(rust_version) = &member.rust_version {
+                text.push_str(&format!(", rust {rust_version}"));
+            }
+            text.push(')');
+            for target in &member.targets {
+                text.push_str(&format!(
+                    "\n  {} {}: {}",
+                    target.kind,
+                    target.name,
+                    target.src_path.display()
+                ));
+            }
+            for (feature, enables) in &member.features {
+                text.push_str(&format!("\n  feature {feature} = [{}]", enables.join(", ")));
+            }
+            for (kind, dependencies) in &member.dependencies {
+                let names: Vec<String> = dependencies
+                    .iter()
+                    .map(|dependency| {
+                        let optional = if dependency.optional {
+                            " (optional)"
+                        } else {
+                            ""
+                        };
+                        format!("{} {}{optional}", dependency.name, dependency.req)
+                    })
+                    .collect();
+                text.push_str(&format!("\n  {kind} dependencies: {}", names.join(", ")));
+            }
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `cargo metadata --format-version 1` for a workspace of three members:
+    /// `app` (the only default member, with a build script, an example, an
+    /// integration test and dev- and build-dependencies), `core-utils` (with
+    /// features, an optional and a platform-specific dependency) and the
+    /// proc-macro crate `macros`.
+    const METADATA: &str = include_str!("testdata/metadata_workspace.json");
+
+    #[test]
+    fn lists_members_and_default_members() {
+        let metadata: Metadata = serde_json::from_str(METADATA).unwrap();
+        let members: Vec<(&str, bool)> = metadata
+            .members()
+            .map(|member| (member.name.as_str(), metadata.is_default_member(member)))
+            .collect();
+        assert_eq!(
+            members,
+            [("app", true), ("core-utils", false), ("macros", false)]
+        );
+        assert!(
+            metadata
+                .packages
+                .iter()
+                .find(|package| package.name == "macros")
+                .unwrap()
+                .is_proc_macro()
+        );
+
+        // Cargo before 1.71 does not report default members.
+        let mut old: serde_json::Value = serde_json::from_str(METADATA).unwrap();
+        old.as_object_mut()
+            .unwrap()
+            .remove("workspace_default_members");
+        let old: Metadata = serde_json::from_value(old).unwrap();
+        assert!(old.members().all(|member| old.is_default_member(member)));
+    }
+
+    #[test]
+    fn summarizes_targets_and_dependencies() {
+        let metadata: Metadata = serde_json::from_str(METADATA).unwrap();
+        let summary = summarize_workspace(&metadata);
+        assert_eq!(summary.workspace_root, Path::new("/tmp/ws"));
+
+        let app = &summary.members[0];
+        assert_eq!(app.manifest_path, Path::new("app/Cargo.toml"));
+        let targets: Vec<(&str, &str, &Path)> = app
+            .targets
+            .iter()
+            .map(|t| (t.kind.as_str(), t.name.as_str(), t.src_path.as_path()))
+            .collect();
+        assert_eq!(
+            targets,
+            [
+                ("bin", "app", Path::new("app/src/main.rs")),
+                ("example", "demo", Path::new("app/examples/demo.rs")),
+                ("test", "it", Path::new("app/tests/it.rs")),
+                (
+                    "custom-build",
+                    "build-script-build",
+                    Path::new("app/build.rs")
+                ),
+            ]
+        );
+        let kinds: Vec<(&str, Vec<&str>)> = app
+            .dependencies
+            .iter()
+            .map(|(kind, deps)| {
+                (
+                    kind.as_str(),
+                    deps.iter().map(|d| d.name.as_str()).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ("build", vec!["autocfg"]),
+                ("dev", vec!["either"]),
+                ("normal", vec!["cfg-if", "core-utils", "macros", "itoa"]),
+            ]
+        );
+        let core_utils = &app.dependencies["normal"][1];
+        assert_eq!(core_utils.path.as_deref(), Some(Path::new("core")));
+        assert_eq!(core_utils.features, ["fast"]);
+        let itoa = &app.dependencies["normal"][3];
+        assert_eq!(
+            (itoa.rename.as_deref(), itoa.req.as_str()),
+            (Some("old_itoa"), "^0.4")
+        );
+
+        let core = &summary.members[1];
+        assert_eq!(core.rust_version.as_deref(), Some("1.70"));
+        assert!(!core.is_default_member);
+        assert_eq!(core.targets[0].crate_types, ["lib"]);
+        assert_eq!(core.features["fast"], ["dep:memchr"]);
+        let libc = core.dependencies["normal"]
+            .iter()
+            .find(|d| d.name == "libc")
+            .unwrap();
+        assert_eq!(libc.target.as_deref(), Some("cfg(unix)"));
+        assert!(!libc.uses_default_features);
+
+        assert_eq!(summary.members[2].targets[0].kind, "lib");
+        assert_eq!(summary.members[2].targets[0].crate_types, ["proc-macro"]);
+    }
+
+    #[test]
+    fn outlines_members() {
+        let metadata: Metadata = serde_json::from_str(METADATA).unwrap();
+        assert_eq!(
+            summarize_workspace(&metadata).outline(),
+            "Workspace at /tmp/ws with 3 members
+
+app 0.1.0 (edition 2021)
+  bin app: app/src/main.rs
+  example demo: app/examples/demo.rs
+  test it: app/tests/it.rs
+  custom-build build-script-build: app/build.rs
+  build dependencies: autocfg ^1
+  dev dependencies: either ^1
+  normal dependencies: cfg-if ^0.1, core-utils *, macros *, itoa ^0.4
+
+core-utils 0.2.0 (edition 2021, rust 1.70)
+  lib core_utils: core/src/lib.rs
+  feature default = [std]
+  feature fast = [dep:memchr]
+  feature std = []
+  normal dependencies: cfg-if ^1, itoa ^1, memchr ^2 (optional), libc ^0.2
+
+macros 0.1.0 (edition 2021)
+  lib macros: macros/src/lib.rs"
+        );
+    }
+}
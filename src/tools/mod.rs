@@ -1,6 +1,26 @@
+pub mod audit;
+pub mod bench;
+pub mod cargo_tools;
+pub mod context;
+pub mod coverage;
 pub mod definitions;
+pub mod dependency_graph;
+pub mod diagnostics;
+pub mod duplicates;
 pub mod executor;
+pub mod fixes;
+pub mod lints;
+pub mod metadata;
+pub mod outdated;
+pub mod output;
+pub mod process;
+pub mod progress;
+pub mod registry_index;
+pub mod schemas;
+pub mod state;
+pub mod test_results;
 pub mod workflow_tools;
+pub mod workspace_queue;
 
 pub use definitions::*;
 pub use executor::*;
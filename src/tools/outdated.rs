@@ -0,0 +1,201 @@
+use semver::{Version, VersionReq};
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::collections::BTreeSet;
+
+use super::dependency_graph::DependencyGraph;
+use super::registry_index::RegistryIndex;
+
+#[derive(Debug, Serialize)]
+pub struct OutdatedDependency {
+    pub name: String,
+    pub locked: String,
+    /// Newest version in the index that is not yanked: within the
+    /// requirements for compatible updates, outside them otherwise.
+    pub available: String,
+    /// Requirements every dependent declares on this package.
+    pub req: Vec<String>,
+    /// Whether the workspace member depends on it itself.
+    pub direct: bool,
+    /// Arguments for the `update` tool that move Cargo.lock to
+    /// `available`; only for compatible updates.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update_args: Option<Value>,
+}
+
+impl std::fmt::Display for OutdatedDependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} -> {}", self.name, self.locked, self.available)?;
+        match (self.direct, self.update_args.is_some()) {
+            (true, true) => write!(f, " (direct)"),
+            (true, false) => write!(f, " (direct, requires {})", self.req.join(", ")),
+            (false, _) => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct MemberOutdated {
+    pub package: String,
+    /// Newer versions every requirement already accepts: `cargo update`
+    /// can pick them up.
+    pub compatible: Vec<OutdatedDependency>,
+    /// Newer versions outside the declared requirements, which need a
+    /// manifest change.
+    pub incompatible: Vec<OutdatedDependency>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OutdatedReport {
+    pub members: Vec<MemberOutdated>,
+    /// Locked registry packages the index cache has no entry for.
+    pub not_in_index: Vec<String>,
+}
+
+/// Newer versions of every locked registry package, per workspace member.
+/// `only` limits the report to packages matching a `name` or
+/// `name@version` spec.
+pub fn find_outdated(
+    graph: &DependencyGraph,
+    index: &mut RegistryIndex,
+    only: Option<&str>,
+) -> OutdatedReport {
+    let selected = only.map(|spec| graph.find(spec));
+    // Available updates for each locked registry package.
+    let mut updates = Vec::new();
+    let mut not_in_index = Vec::new();
+    for node in &graph.nodes {
+        let Some(source) = node
+            .source
+            .as_deref()
+            .filter(|source| source.starts_with("registry+") || source.starts_with("sparse+"))
+        else {
+            continue;
+        };
+        if selected.as_ref().is_some_and(|ids| !ids.contains(&node.id)) {
+            continue;
+        }
+        let Ok(locked) = Version::parse(&node.version) else {
+            continue;
+        };
+        let Some(published) = index.versions(source, &node.name) else {
+            not_in_index.push(node.label());
+            continue;
+        };
+
+        let req: Vec<String> = graph
+            .edges_to(&node.id)
+            .flat_map(|edge| edge.req.iter().cloned())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let parsed: Vec<VersionReq> = req
+            .iter()
+            .filter_map(|req| VersionReq::parse(req).ok())
+            .collect();
+        let candidates = published.iter().filter(|candidate| {
+            !candidate.yanked
+                && candidate.version > locked
+                && (candidate.version.pre.is_empty() || !locked.pre.is_empty())
+        });
+        let compatible = candidates
+            .clone()
+            .filter(|candidate| parsed.iter().all(|req| req.matches(&candidate.version)))
+            .map(|candidate| &candidate.version)
+            .max();
+        let latest = candidates
+            .filter(|candidate| !parsed.iter().all(|req| req.matches(&candidate.version)))
+            .map(|candidate| &candidate.version)
+            .max()
+            .filter(|latest| compatible.is_none_or(|compatible| *latest > compatible));
+
+        updates.push((node, req, compatible.cloned(), latest.cloned()));
+    }
+
+    let members = graph
+        .nodes
+        .iter()
+        .filter(|node| node.workspace_member)
+        .map(|member| {
+            let descendants = graph.descendants(&member.id);
+            let mut report = MemberOutdated {
+                package: member.label(),
+                compatible: Vec::new(),
+                incompatible: Vec::new(),
+            };
+            for (node, req, compatible, latest) in &updates {
+                if !descendants.contains(&node.id) {
+                    continue;
+                }
+                let direct = graph.edges_to(&node.id).any(|edge| edge.from == member.id);
+                let entry = |available: &Version, update_args| OutdatedDependency {
+                    name: node.name.clone(),
+                    locked: node.version.clone(),
+                    available: available.to_string(),
+                    req: req.clone(),
+                    direct,
+                    update_args,
+                };
+                if let Some(compatible) = compatible {
+                    report.compatible.push(entry(
+                        compatible,
+                        Some(json!({
+                            "package": node.label(),
+                            "precise": compatible.to_string(),
+                        })),
+                    ));
+                }
+                if let Some(latest) = latest {
+                    report.incompatible.push(entry(latest, None));
+                }
+            }
+            report
+        })
+        .collect();
+
+    OutdatedReport {
+        members,
+        not_in_index,
+    }
+}
+
+impl OutdatedReport {
+    pub fn summary(&self) -> String {
+        let mut text = String::new();
+        for member in &self.members {
+            if !text.is_empty() {
+                text.push_str("\n\n");
+            }
+            text.push_str(&format!(
+                "{}: {} compatible, {} incompatible update{}",
+                member.package,
+                member.compatible.len(),
+                member.incompatible.len(),
+                if member.incompatible.len() == 1 {
+                    ""
+                } else {
+                    "s"
+                }
+            ));
+            for (title, dependencies) in [
+                ("compatible", &member.compatible),
+                ("incompatible", &member.incompatible),
+            ] {
+                if dependencies.is_empty() {
+                    continue;
+                }
+                text.push_str(&format!("\n  {title}:"));
+                for dependency in dependencies {
+                    text.push_str(&format!("\n    {dependency}"));
+                }
+            }
+        }
+        if !self.not_in_index.is_empty() {
+            text.push_str(&format!(
+                "\n\nNot in the local index cache: {}",
+                self.not_in_index.join(", ")
+            ));
+        }
+        text
+    }
+}
@@ -0,0 +1,67 @@
+use serde_json::{Map, Value, json};
+
+use super::process::CommandOutput;
+
+/// Build a tool result from a human-readable summary and the structured
+/// payload described by the tool's `outputSchema`.
+///
+/// `handle_tool_call` later adds `duration_ms` and appends the serialized
+/// payload as a second text block for clients without structured output.
+pub fn structured_result(text: String, success: bool, structured: Value) -> Value {
+    json!({
+        "content": [{
+            "type": "text",
+            "text": text
+        }],
+        "structuredContent": structured,
+        "isError": !success
+    })
+}
+
+/// Structured result of a single cargo invocation: the common fields every
+/// command reports, merged with tool-specific `extra` fields. The captured
+/// output is included when the command failed, since the summary is then
+/// the only other place it would appear.
+pub fn command_result(text: String, output: &CommandOutput, extra: Value) -> Value {
+    let success = output.success();
+    let mut structured = Map::new();
+    structured.insert("success".to_string(), json!(success));
+    structured.insert("exit_code".to_string(), json!(output.exit_code()));
+    structured.insert("timed_out".to_string(), json!(output.timed_out));
+    structured.insert("command".to_string(), json!(output.command));
+    if !success {
+        structured.insert("stdout".to_string(), json!(output.stdout));
+        structured.insert("stderr".to_string(), json!(output.stderr));
+    }
+    if let Value::Object(extra) = extra {
+        structured.extend(extra);
+    }
+
+    structured_result(text, success, Value::Object(structured))
+}
+
+/// Append `extra` to the human-readable summary of a result.
+pub fn append_text(result: &mut Value, extra: &str) {
+    if let Some(text) = result["content"][0]["text"].as_str() {
+        result["content"][0]["text"] = json!(format!("{text}{extra}"));
+    }
+}
+
+/// Add the call duration and the serialized structured payload to a result
+/// produced by one of the builders above.
+pub fn finalize_result(result: &mut Value, duration_ms: u128) {
+    let Some(structured) = result.get_mut("structuredContent") else {
+        return;
+    };
+    if let Some(structured) = structured.as_object_mut() {
+        structured.insert("duration_ms".to_string(), json!(duration_ms));
+    }
+    let serialized = structured.to_string();
+
+    if let Some(content) = result.get_mut("content").and_then(Value::as_array_mut) {
+        content.push(json!({
+            "type": "text",
+            "text": serialized
+        }));
+    }
+}
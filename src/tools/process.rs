@@ -0,0 +1,146 @@
+use anyhow::{Context, Result, anyhow};
+use std::process::{ExitStatus, Stdio};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+
+use super::context::ToolContext;
+use super::progress::ProgressReporter;
+
+/// Captured result of a child process.
+#[derive(Debug)]
+pub struct CommandOutput {
+    /// The command line, for reporting.
+    pub command: String,
+    pub stdout: String,
+    pub stderr: String,
+    /// `None` when the process was killed because it timed out.
+    pub status: Option<ExitStatus>,
+    pub timed_out: bool,
+}
+
+impl CommandOutput {
+    pub fn success(&self) -> bool {
+        self.status.is_some_and(|status| status.success())
+    }
+
+    pub fn exit_code(&self) -> Option<i32> {
+        self.status.and_then(|status| status.code())
+    }
+
+    /// Stdout and stderr joined the way the tools have always reported them.
+    pub fn combined(&self) -> String {
+        format!("{}{}", self.stdout, self.stderr)
+    }
+}
+
+/// Read a pipe to the end, line by line, so progress can be reported while
+/// the child is still running.
+async fn read_all<R: AsyncRead + Unpin>(
+    reader: Option<R>,
+    progress: Option<Arc<ProgressReporter>>,
+) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    let Some(reader) = reader else {
+        return buffer;
+    };
+    let mut reader = BufReader::new(reader);
+    loop {
+        let start = buffer.len();
+        // A read error only means the pipe went away; keep what we have.
+        match reader.read_until(b'\n', &mut buffer).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {
+                if let Some(progress) = &progress {
+                    progress.observe_line(&String::from_utf8_lossy(&buffer[start..]));
+                }
+            }
+        }
+    }
+    buffer
+}
+
+/// Render a command line, quoting arguments that contain whitespace.
+fn describe_command(cmd: &Command) -> String {
+    let cmd = cmd.as_std();
+    std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(|arg| {
+            let arg = arg.to_string_lossy();
+            if arg.contains(char::is_whitespace) || arg.is_empty() {
+                format!("{arg:?}")
+            } else {
+                arg.into_owned()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Kill the child and everything it spawned (rustc, test binaries, ...).
+fn kill_process_tree(child: &mut Child) {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        // SAFETY: killpg has no memory-safety preconditions; the child was
+        // started as the leader of its own process group.
+        unsafe {
+            libc::killpg(pid as libc::pid_t, libc::SIGKILL);
+        }
+    }
+    let _ = child.start_kill();
+}
+
+/// Run a command to completion without blocking the runtime, killing its
+/// whole process group if it outlives the context's timeout or the call is
+/// cancelled. Cancellation is reported as an error so multi-step handlers
+/// stop at the next command.
+pub async fn run_command(mut cmd: Command, ctx: &ToolContext) -> Result<CommandOutput> {
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(unix)]
+    cmd.process_group(0);
+
+    let command = describe_command(&cmd);
+
+    if ctx.cancellation.is_cancelled() {
+        return Err(anyhow!("Cancelled by client"));
+    }
+
+    let mut child = cmd.spawn().context("Failed to spawn process")?;
+    let stdout_task = tokio::spawn(read_all(child.stdout.take(), ctx.progress.clone()));
+    let stderr_task = tokio::spawn(read_all(child.stderr.take(), ctx.progress.clone()));
+
+    let deadline = async {
+        match ctx.timeout {
+            Some(timeout) => tokio::time::sleep(timeout).await,
+            None => std::future::pending().await,
+        }
+    };
+
+    let status = tokio::select! {
+        status = child.wait() => Some(status.context("Failed to wait for process")?),
+        () = deadline => {
+            kill_process_tree(&mut child);
+            let _ = child.wait().await;
+            None
+        }
+        () = ctx.cancellation.cancelled() => {
+            kill_process_tree(&mut child);
+            let _ = child.wait().await;
+            return Err(anyhow!("Cancelled by client"));
+        }
+    };
+
+    let stdout = stdout_task.await.context("Failed to read stdout")?;
+    let stderr = stderr_task.await.context("Failed to read stderr")?;
+
+    Ok(CommandOutput {
+        command,
+        stdout: String::from_utf8_lossy(&stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&stderr).into_owned(),
+        timed_out: status.is_none(),
+        status,
+    })
+}
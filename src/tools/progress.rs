@@ -0,0 +1,231 @@
+use serde_json::{Value, json};
+use std::sync::Mutex;
+use tokio::sync::mpsc;
+
+use crate::protocol::ProtocolVersion;
+use crate::types::{McpMessage, McpNotification};
+
+#[derive(Debug, Default)]
+struct ProgressState {
+    /// Crates cargo has started compiling, checking or documenting.
+    crates: u64,
+    /// Tests that reported a result.
+    tests: u64,
+    /// Total announced by the current test suite, if any.
+    total: Option<u64>,
+}
+
+impl ProgressState {
+    fn progress(&self) -> u64 {
+        self.crates + self.tests
+    }
+}
+
+/// Turns cargo and libtest output lines into `notifications/progress`
+/// messages for the client's `progressToken`.
+#[derive(Debug)]
+pub struct ProgressReporter {
+    token: Value,
+    /// Negotiated with the client; decides whether `message` is sent.
+    version: ProtocolVersion,
+    outgoing: mpsc::UnboundedSender<McpMessage>,
+    state: Mutex<ProgressState>,
+}
+
+/// Cargo status verbs that mark a crate being built.
+const BUILD_VERBS: [&str; 3] = ["Compiling", "Checking", "Documenting"];
+
+impl ProgressReporter {
+    pub fn new(
+        token: Value,
+        version: ProtocolVersion,
+        outgoing: mpsc::UnboundedSender<McpMessage>,
+    ) -> Self {
+        Self {
+            token,
+            version,
+            outgoing,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Inspect one line of child output and notify the client if it marks
+    /// progress. Lines that do not are ignored.
+    pub fn observe_line(&self, line: &str) {
+        let line = line.trim();
+        let mut state = self.state.lock().unwrap_or_else(|p| p.into_inner());
+
+        let message = if let Some((verb, rest)) = line.split_once(' ')
+            && BUILD_VERBS.contains(&verb)
+        {
+            state.crates += 1;
+            // "serde v1.0.0 (/path)" -> "serde v1.0.0"
+            let krate: Vec<&str> = rest.split_whitespace().take(2).collect();
+            format!(
+                "{verb} {} ({} crates built)",
+                krate.join(" "),
+                state.crates - 1
+            )
+        } else if let Some(count) = line
+            .strip_prefix("running ")
+            .and_then(|rest| rest.strip_suffix(" tests").or(rest.strip_suffix(" test")))
+            .and_then(|count| count.parse::<u64>().ok())
+        {
+            // Progress must increase with every notification, so the total
+            // is only announced along with the first test result.
+            state.total = Some(state.progress() + count);
+            return;
+        } else if line.starts_with('{') {
+            let Ok(event) = serde_json::from_str::<Value>(line) else {
+                return;
+            };
+            match (event["type"].as_str(), event["event"].as_str()) {
+                (Some("suite"), Some("started")) => {
+                    let count = event["test_count"].as_u64().unwrap_or_default();
+                    state.total = Some(state.progress() + count);
+                    return;
+                }
+                (Some("test"), Some(outcome @ ("ok" | "failed" | "ignored"))) => {
+                    state.tests += 1;
+                    format!("{}: {outcome}", event["name"].as_str().unwrap_or_default())
+                }
+                _ => return,
+            }
+        } else if let Some(rest) = line.strip_prefix("test ")
+            && let Some((name, outcome)) = rest.rsplit_once(" ... ")
+        {
+            state.tests += 1;
+            format!("{name}: {outcome}")
+        } else {
+            return;
+        };
+
+        self.notify(&state, message);
+    }
+
+    fn notify(&self, state: &ProgressState, message: String) {
+        let mut params = json!({
+            "progressToken": self.token,
+            "progress": state.progress(),
+        });
+        if let Some(total) = state.total {
+            params["total"] = json!(total);
+        }
+        if self.version.supports_progress_messages() {
+            params["message"] = json!(message);
+        }
+        let _ = self
+            .outgoing
+            .send(McpMessage::Notification(McpNotification {
+                jsonrpc: "2.0".to_string(),
+                method: "notifications/progress".to_string(),
+                params: Some(params),
+            }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `cargo test --no-fail-fast` on a crate with unit tests (one passing,
+    // one failing, one ignored), an integration test and a doctest.
+    const PLAIN_STDOUT: &str = include_str!("testdata/test_plain.stdout");
+    const JSON_STDOUT: &str = include_str!("testdata/test_json.stdout");
+    const JSON_STDERR: &str = include_str!("testdata/test_json.stderr");
+
+    /// The params of every notification sent for `lines`.
+    fn notifications(version: ProtocolVersion, lines: &[&str]) -> Vec<Value> {
+        let (outgoing, mut incoming) = mpsc::unbounded_channel();
+        let reporter = ProgressReporter::new(json!("token"), version, outgoing);
+        for line in lines {
+            reporter.observe_line(line);
+        }
+        drop(reporter);
+
+        let mut params = Vec::new();
+        while let Ok(message) = incoming.try_recv() {
+            let McpMessage::Notification(notification) = message else {
+                panic!("expected a notification");
+            };
+            assert_eq!(notification.method, "notifications/progress");
+            params.push(notification.params.unwrap());
+        }
+        params
+    }
+
+    fn progress(params: &[Value]) -> Vec<(u64, Option<u64>, &str)> {
+        params
+            .iter()
+            .map(|params| {
+                assert_eq!(params["progressToken"], "token");
+                (
+                    params["progress"].as_u64().unwrap(),
+                    params["total"].as_u64(),
+                    params["message"].as_str().unwrap_or_default(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reports_crates_and_plain_test_results() {
+        let lines: Vec<&str> = JSON_STDERR.lines().chain(PLAIN_STDOUT.lines()).collect();
+        let params = notifications(ProtocolVersion::LATEST, &lines);
+        assert_eq!(
+            progress(&params),
+            [
+                (1, None, "Compiling lt v0.1.0 (0 crates built)"),
+                (2, Some(4), "tests::adds: ok"),
+                (3, Some(4), "tests::fails: FAILED"),
+                (4, Some(4), "tests::slow: ignored"),
+                (5, Some(5), "api_works: ok"),
+                (6, Some(6), "src/lib.rs - add (line 1): ok"),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_json_test_events() {
+        let lines: Vec<&str> = JSON_STDOUT.lines().collect();
+        let params = notifications(ProtocolVersion::LATEST, &lines);
+        let progress = progress(&params);
+        assert_eq!(
+            progress[..3],
+            [
+                (1, Some(3), "tests::adds: ok"),
+                (2, Some(3), "tests::fails: failed"),
+                (3, Some(3), "tests::slow: ignored"),
+            ]
+        );
+        // Progress only ever increases.
+        assert!(progress.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn ignores_other_lines() {
+        let lines = [
+            "    Finished `test` profile [unoptimized + debuginfo] target(s) in 0.36s",
+            "test result: ok. 1 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out",
+            "{ \"type\": \"test\", \"event\": \"started\", \"name\": \"tests::adds\" }",
+            "{ not json",
+            "running many tests",
+        ];
+        assert!(notifications(ProtocolVersion::LATEST, &lines).is_empty());
+    }
+
+    #[test]
+    fn omits_messages_before_2025_03_26() {
+        let lines = [
+            "   Compiling lt v0.1.0 (/tmp/lt)",
+            "test tests::adds ... ok",
+        ];
+        let params = notifications(ProtocolVersion::V2024_11_05, &lines);
+        assert_eq!(params.len(), 2);
+        assert!(params.iter().all(|params| params.get("message").is_none()));
+        assert_eq!(params[1]["progress"], 2);
+
+        let params = notifications(ProtocolVersion::V2025_03_26, &lines);
+        assert_eq!(params[1]["message"], "tests::adds: ok");
+    }
+}
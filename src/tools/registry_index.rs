@@ -0,0 +1,281 @@
+use semver::Version;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Cache file layout cargo currently writes under `<index>/.cache`.
+const CACHE_VERSION: u8 = 3;
+
+/// Registry sources that refer to crates.io, over git or sparse HTTP.
+const CRATES_IO_SOURCES: &[&str] = &[
+    "registry+https://github.com/rust-lang/crates.io-index",
+    "sparse+https://index.crates.io/",
+];
+
+/// Index directory prefixes cargo uses for crates.io, most current first:
+/// the sparse index is the default since Cargo 1.70, the git index cache
+/// may linger from older toolchains.
+const CRATES_IO_DIRS: &[&str] = &["index.crates.io-", "github.com-"];
+
+#[derive(Debug, Clone)]
+pub struct IndexVersion {
+    pub version: Version,
+    pub yanked: bool,
+}
+
+#[derive(Deserialize)]
+struct IndexEntry {
+    vers: String,
+    #[serde(default)]
+    yanked: bool,
+}
+
+/// Read-only view of the index caches cargo keeps under
+/// `$CARGO_HOME/registry/index`, so versions can be looked up without
+/// network access. Only crates cargo has resolved before are cached.
+pub struct RegistryIndex {
+    root: PathBuf,
+    versions: HashMap<(String, String), Option<Vec<IndexVersion>>>,
+}
+
+impl RegistryIndex {
+    /// The index of the cargo home in `CARGO_HOME`, or `~/.cargo`.
+    pub fn open() -> Option<Self> {
+        let root = cargo_home()?.join("registry").join("index");
+        root.is_dir().then(|| Self {
+            root,
+            versions: HashMap::new(),
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Published versions of `name` from the registry `source`, or `None`
+    /// when the cache has no entry for it. Versions from every index
+    /// directory of the source are merged, so a stale cache cannot hide
+    /// newer releases; the most current directory decides yanked flags.
+    pub fn versions(&mut self, source: &str, name: &str) -> Option<&[IndexVersion]> {
+        let key = (source.to_string(), name.to_string());
+        if !self.versions.contains_key(&key) {
+            let mut versions: Option<Vec<IndexVersion>> = None;
+            for dir in self.index_dirs(source) {
+                let Some(cached) = read_cache_file(&dir.join(".cache").join(cache_path(name)))
+                else {
+                    continue;
+                };
+                let merged = versions.get_or_insert_with(Vec::new);
+                for version in cached {
+                    if !merged.iter().any(|known| known.version == version.version) {
+                        merged.push(version);
+                    }
+                }
+            }
+            self.versions.insert(key.clone(), versions);
+        }
+        self.versions[&key].as_deref()
+    }
+
+    /// Index directories that may hold `source`, most current first.
+    /// Directory names are the registry host followed by a hash of its URL.
+    fn index_dirs(&self, source: &str) -> Vec<PathBuf> {
+        let prefixes: Vec<String> = if CRATES_IO_SOURCES.contains(&source) {
+            CRATES_IO_DIRS
+                .iter()
+                .map(|prefix| prefix.to_string())
+                .collect()
+        } else {
+            let url = source.split_once('+').map_or(source, |(_, url)| url);
+            let host = url
+                .split_once("://")
+                .map_or(url, |(_, rest)| rest)
+                .split(['/', ':'])
+                .next()
+                .unwrap_or_default();
+            vec![format!("{host}-")]
+        };
+
+        let Ok(entries) = std::fs::read_dir(&self.root) else {
+            return Vec::new();
+        };
+        let mut dirs: Vec<(usize, PathBuf)> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                let rank = prefixes
+                    .iter()
+                    .position(|prefix| name.starts_with(prefix.as_str()))?;
+                Some((rank, entry.path()))
+            })
+            .collect();
+        dirs.sort();
+        dirs.into_iter().map(|(_, dir)| dir).collect()
+    }
+}
+
+/// `CARGO_HOME`, or `~/.cargo`.
+pub fn cargo_home() -> Option<PathBuf> {
+    std::env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cargo")))
+}
+
+/// Location of a crate's file inside an index: `1/a`, `2/ab`, `3/a/abc`,
+/// then `ab/cd/abcd...`, all lowercase.
+fn cache_path(name: &str) -> PathBuf {
+    let name = name.to_lowercase();
+    match name.len() {
+        1 => Path::new("1").join(&name),
+        2 => Path::new("2").join(&name),
+        3 => Path::new("3").join(&name[..1]).join(&name),
+        _ => Path::new(&name[..2]).join(&name[2..4]).join(&name),
+    }
+}
+
+/// Parse a cache file: a version byte, the index format as a
+/// little-endian u32, a NUL-terminated freshness marker (etag or commit),
+/// then NUL-terminated pairs of version and JSON entry.
+fn read_cache_file(path: &Path) -> Option<Vec<IndexVersion>> {
+    parse_cache(&std::fs::read(path).ok()?)
+}
+
+fn parse_cache(contents: &[u8]) -> Option<Vec<IndexVersion>> {
+    if contents.first() != Some(&CACHE_VERSION) || contents.len() < 5 {
+        return None;
+    }
+    let mut fields = contents[5..].split(|byte| *byte == 0);
+    fields.next()?;
+
+    let mut versions = Vec::new();
+    while let (Some(_), Some(entry)) = (fields.next(), fields.next()) {
+        let Ok(entry) = serde_json::from_slice::<IndexEntry>(entry) else {
+            continue;
+        };
+        if let Ok(version) = Version::parse(&entry.vers) {
+            versions.push(IndexVersion {
+                version,
+                yanked: entry.yanked,
+            });
+        }
+    }
+    Some(versions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cargo's cache entry for `qoi`, whose five oldest releases are yanked.
+    const QOI_CACHE: &[u8] = include_bytes!("testdata/qoi.cache");
+
+    #[test]
+    fn parses_cached_versions() {
+        let versions = parse_cache(QOI_CACHE).unwrap();
+        let versions: Vec<(String, bool)> = versions
+            .iter()
+            .map(|v| (v.version.to_string(), v.yanked))
+            .collect();
+        assert_eq!(
+            versions,
+            [
+                ("0.0.1".to_string(), true),
+                ("0.1.0".to_string(), true),
+                ("0.2.0".to_string(), true),
+                ("0.3.0".to_string(), true),
+                ("0.3.1".to_string(), true),
+                ("0.4.0".to_string(), false),
+                ("0.4.1".to_string(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_other_cache_versions() {
+        let mut contents = QOI_CACHE.to_vec();
+        contents[0] = CACHE_VERSION + 1;
+        assert!(parse_cache(&contents).is_none());
+        assert!(parse_cache(&[CACHE_VERSION]).is_none());
+    }
+
+    #[test]
+    fn skips_unparsable_entries() {
+        let contents = b"\x03\x02\x00\x00\x00etag\x001.0.0\x00{\"vers\":\"1.0.0\"}\x00\
+            x\x00not json\x002.0\x00{\"vers\":\"2.0\"}\x00";
+        let versions = parse_cache(contents).unwrap();
+        assert_eq!(versions.len(), 1);
+        assert_eq!(versions[0].version, Version::new(1, 0, 0));
+        assert!(!versions[0].yanked);
+    }
+
+    /// A cache file listing `versions` as `(version, yanked)`.
+    fn cache_file(versions: &[(&str, bool)]) -> Vec<u8> {
+        let mut contents = vec![CACHE_VERSION, 2, 0, 0, 0];
+        contents.extend_from_slice(b"etag\0");
+        for (version, yanked) in versions {
+            let entry = format!(r#"{{"vers":"{version}","yanked":{yanked}}}"#);
+            contents.extend_from_slice(version.as_bytes());
+            contents.push(0);
+            contents.extend_from_slice(entry.as_bytes());
+            contents.push(0);
+        }
+        contents
+    }
+
+    #[test]
+    fn merges_sparse_and_git_index_caches() {
+        let root = std::env::temp_dir().join(format!("cargo-mcp-index-{}", std::process::id()));
+        let write = |dir: &str, versions: &[(&str, bool)]| {
+            let path = root.join(dir).join(".cache").join(cache_path("qoi"));
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, cache_file(versions)).unwrap();
+        };
+        // The git index was last fetched before 0.4.1 and the 0.4.0 yank.
+        write(
+            "github.com-1ecc6299db9ec823",
+            &[("0.3.1", false), ("0.4.0", false)],
+        );
+        write(
+            "index.crates.io-6f17d22bba15001f",
+            &[("0.4.0", true), ("0.4.1", false)],
+        );
+        std::fs::create_dir_all(root.join("my-registry.example.com-0123456789abcdef")).unwrap();
+
+        let mut index = RegistryIndex {
+            root: root.clone(),
+            versions: HashMap::new(),
+        };
+        let versions: Vec<(String, bool)> = index
+            .versions(
+                "registry+https://github.com/rust-lang/crates.io-index",
+                "qoi",
+            )
+            .unwrap()
+            .iter()
+            .map(|v| (v.version.to_string(), v.yanked))
+            .collect();
+        assert_eq!(
+            versions,
+            [
+                ("0.4.0".to_string(), true),
+                ("0.4.1".to_string(), false),
+                ("0.3.1".to_string(), false),
+            ]
+        );
+        assert!(
+            index
+                .versions("sparse+https://my-registry.example.com/index/", "qoi")
+                .is_none()
+        );
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn lays_out_cache_paths_by_name_length() {
+        assert_eq!(cache_path("a"), Path::new("1/a"));
+        assert_eq!(cache_path("cc"), Path::new("2/cc"));
+        assert_eq!(cache_path("Qoi"), Path::new("3/q/qoi"));
+        assert_eq!(cache_path("Serde_JSON"), Path::new("se/rd/serde_json"));
+    }
+}
@@ -0,0 +1,648 @@
+use serde_json::{Map, Value, json};
+
+/// Fields every tool result carries in `structuredContent`.
+fn common_output_properties() -> Map<String, Value> {
+    let properties = json!({
+        "success": {
+            "type": "boolean",
+            "description": "Whether the operation succeeded"
+        },
+        "exit_code": {
+            "type": ["integer", "null"],
+            "description": "Exit code of the cargo process, null if it was killed"
+        },
+        "timed_out": {
+            "type": "boolean",
+            "description": "Whether cargo was killed after exceeding timeout_secs"
+        },
+        "duration_ms": {
+            "type": "integer",
+            "description": "Wall-clock duration of the tool call in milliseconds"
+        },
+        "command": {
+            "type": "string",
+            "description": "Command line that was executed"
+        },
+        "stdout": {
+            "type": "string",
+            "description": "Captured standard output, present when the command failed"
+        },
+        "stderr": {
+            "type": "string",
+            "description": "Captured standard error, present when the command failed"
+        }
+    });
+    properties.as_object().cloned().unwrap_or_default()
+}
+
+/// Output schema made of the common fields plus tool-specific `extra`
+/// properties.
+pub fn output_schema(extra: Value) -> Option<Value> {
+    let mut properties = common_output_properties();
+    if let Value::Object(extra) = extra {
+        properties.extend(extra);
+    }
+
+    Some(json!({
+        "type": "object",
+        "properties": properties,
+        "required": ["success"]
+    }))
+}
+
+fn span_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "file": {"type": "string"},
+            "line": {"type": "integer"},
+            "column": {"type": "integer"},
+            "end_line": {"type": "integer"},
+            "end_column": {"type": "integer"}
+        }
+    })
+}
+
+pub fn diagnostics_properties() -> Value {
+    json!({
+        "diagnostics": {
+            "type": "array",
+            "description": "Compiler diagnostics, deduplicated across targets",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "string",
+                        "description": "Pass to apply_fixes to apply this diagnostic's machine-applicable suggestions"
+                    },
+                    "level": {"type": "string"},
+                    "code": {"type": "string"},
+                    "message": {"type": "string"},
+                    "span": span_schema(),
+                    "labels": {"type": "array", "items": {"type": "object"}},
+                    "children": {"type": "array", "items": {"type": "object"}},
+                    "suggestions": {"type": "array", "items": {"type": "object"}}
+                },
+                "required": ["level", "message"]
+            }
+        },
+        "error_count": {"type": "integer"},
+        "warning_count": {"type": "integer"},
+        "explanations": {
+            "type": "object",
+            "description": "rustc --explain text per distinct error code, when explain_errors is set",
+            "additionalProperties": {"type": "string"}
+        }
+    })
+}
+
+/// Diagnostics plus the lint levels `lint` ran with.
+pub fn lint_properties() -> Value {
+    let mut properties = diagnostics_properties();
+    properties["lint_levels"] = json!({
+        "type": "array",
+        "description": "Effective lint levels per linted package, in the order they apply",
+        "items": {
+            "type": "object",
+            "properties": {
+                "package": {"type": "string"},
+                "lints": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "lint": {"type": "string"},
+                            "level": {"type": "string", "enum": ["allow", "warn", "deny", "forbid"]},
+                            "source": {"type": "string", "enum": ["manifest", "command_line"]}
+                        }
+                    }
+                }
+            }
+        }
+    });
+    properties["lint_levels_error"] = json!({
+        "type": "string",
+        "description": "Why lint_levels is missing: the manifests could not be read"
+    });
+    properties
+}
+
+/// The workspace layout returned by `workspace_info`.
+pub fn workspace_properties() -> Value {
+    let dependency = json!({
+        "type": "object",
+        "properties": {
+            "name": {"type": "string"},
+            "req": {"type": "string"},
+            "optional": {"type": "boolean"},
+            "uses_default_features": {"type": "boolean"},
+            "features": {"type": "array", "items": {"type": "string"}},
+            "target": {"type": "string"},
+            "rename": {"type": "string"},
+            "path": {"type": "string"}
+        }
+    });
+    json!({
+        "workspace_root": {"type": "string"},
+        "target_directory": {"type": "string"},
+        "members": {
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "name": {"type": "string"},
+                    "version": {"type": "string"},
+                    "manifest_path": {"type": "string"},
+                    "edition": {"type": "string"},
+                    "rust_version": {"type": "string"},
+                    "is_default_member": {"type": "boolean"},
+                    "targets": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "name": {"type": "string"},
+                                "kind": {"type": "string", "description": "lib, bin, example, test, bench or custom-build"},
+                                "crate_types": {"type": "array", "items": {"type": "string"}},
+                                "src_path": {"type": "string"},
+                                "required_features": {"type": "array", "items": {"type": "string"}}
+                            }
+                        }
+                    },
+                    "features": {
+                        "type": "object",
+                        "description": "Each feature and what it enables",
+                        "additionalProperties": {"type": "array", "items": {"type": "string"}}
+                    },
+                    "dependencies": {
+                        "type": "object",
+                        "description": "Declared dependencies by kind: normal, dev, build",
+                        "additionalProperties": {"type": "array", "items": dependency}
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Nodes and edges returned by `dependency_graph`.
+pub fn dependency_graph_properties() -> Value {
+    json!({
+        "roots": {
+            "type": "array",
+            "description": "Package ids the graph starts from",
+            "items": {"type": "string"}
+        },
+        "nodes": {
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "id": {"type": "string"},
+                    "name": {"type": "string"},
+                    "version": {"type": "string"},
+                    "source": {"type": "string"},
+                    "workspace_member": {"type": "boolean"},
+                    "features": {"type": "array", "items": {"type": "string"}}
+                }
+            }
+        },
+        "edges": {
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "from": {"type": "string"},
+                    "to": {"type": "string"},
+                    "name": {"type": "string"},
+                    "kinds": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "kind": {"type": "string", "enum": ["normal", "dev", "build"]},
+                                "target": {"type": "string"}
+                            }
+                        }
+                    },
+                    "req": {"type": "array", "items": {"type": "string"}},
+                    "activated_by": {
+                        "type": "array",
+                        "description": "Features of the dependent that enabled this optional dependency",
+                        "items": {"type": "string"}
+                    },
+                    "features": {
+                        "type": "array",
+                        "description": "Features the dependent enables on the dependency",
+                        "items": {"type": "string"}
+                    }
+                }
+            }
+        },
+        "paths": {
+            "type": "array",
+            "description": "For why: every path from the roots to the package, as name@version",
+            "items": {"type": "array", "items": {"type": "string"}}
+        },
+        "truncated": {"type": "boolean"}
+    })
+}
+
+/// The report returned by `duplicate_dependencies`.
+pub fn duplicates_properties() -> Value {
+    json!({
+        "duplicate_count": {"type": "integer"},
+        "duplicates": {
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "name": {"type": "string"},
+                    "versions": {
+                        "type": "array",
+                        "description": "Oldest first; the others could unify on the last one",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "version": {"type": "string"},
+                                "dependents": {
+                                    "type": "array",
+                                    "items": {
+                                        "type": "object",
+                                        "properties": {
+                                            "package": {"type": "string"},
+                                            "req": {"type": "array", "items": {"type": "string"}},
+                                            "accepts_target": {"type": "boolean"}
+                                        }
+                                    }
+                                },
+                                "pulled_in_by": {
+                                    "type": "array",
+                                    "description": "Direct dependencies of the workspace members leading to this version",
+                                    "items": {"type": "string"}
+                                },
+                                "hint": {
+                                    "type": "object",
+                                    "properties": {
+                                        "action": {"type": "string", "enum": ["update_precise", "bump_dependency", "none"]},
+                                        "command": {"type": "string"},
+                                        "package": {"type": "string"},
+                                        "message": {"type": "string"}
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// The report returned by `outdated`.
+pub fn outdated_properties() -> Value {
+    let dependencies = json!({
+        "type": "array",
+        "items": {
+            "type": "object",
+            "properties": {
+                "name": {"type": "string"},
+                "locked": {"type": "string"},
+                "available": {"type": "string"},
+                "req": {"type": "array", "items": {"type": "string"}},
+                "direct": {"type": "boolean"},
+                "update_args": {
+                    "type": "object",
+                    "description": "Arguments for the update tool that lock the available version"
+                }
+            }
+        }
+    });
+    json!({
+        "index": {"type": "string", "description": "Registry index directory the versions were read from"},
+        "members": {
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "package": {"type": "string"},
+                    "compatible": dependencies.clone(),
+                    "incompatible": dependencies
+                }
+            }
+        },
+        "not_in_index": {
+            "type": "array",
+            "description": "Locked packages the local index cache has no entry for",
+            "items": {"type": "string"}
+        }
+    })
+}
+
+/// The findings returned by `audit`.
+pub fn audit_properties() -> Value {
+    json!({
+        "advisory_db": {"type": "string", "description": "Advisory database checkout that was read"},
+        "packages_checked": {"type": "integer"},
+        "advisories_checked": {"type": "integer", "description": "Advisories for crates in Cargo.lock"},
+        "findings": {
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "id": {"type": "string", "description": "RUSTSEC advisory id"},
+                    "kind": {"type": "string", "description": "vulnerability, unmaintained, unsound or notice"},
+                    "package": {"type": "string"},
+                    "version": {"type": "string", "description": "Locked version"},
+                    "title": {"type": "string"},
+                    "date": {"type": "string"},
+                    "url": {"type": "string"},
+                    "aliases": {"type": "array", "items": {"type": "string"}},
+                    "affected_versions": {"type": "string"},
+                    "patched_versions": {"type": "array", "items": {"type": "string"}},
+                    "unaffected_versions": {"type": "array", "items": {"type": "string"}},
+                    "paths": {
+                        "type": "array",
+                        "description": "Dependency paths from the workspace members, as name@version",
+                        "items": {"type": "array", "items": {"type": "string"}}
+                    },
+                    "fix": {
+                        "type": "object",
+                        "description": "update: call update with arguments; add_crate: call add_crate with each of arguments; upgrade_dependents: upgrade blocked_by; replace: no patched release",
+                        "properties": {
+                            "action": {"type": "string", "enum": ["update", "add_crate", "upgrade_dependents", "replace"]},
+                            "arguments": {},
+                            "blocked_by": {"type": "array", "items": {"type": "string"}},
+                            "message": {"type": "string"}
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// The explanation returned by `explain_error`.
+pub fn explanation_properties() -> Value {
+    json!({
+        "code": {"type": "string"},
+        "explanation": {"type": "string", "description": "Markdown from rustc --explain"}
+    })
+}
+
+pub fn test_count_properties() -> Value {
+    json!({
+        "tests": {
+            "type": "object",
+            "description": "Totals over every test binary that ran",
+            "properties": {
+                "passed": {"type": "integer"},
+                "failed": {"type": "integer"},
+                "ignored": {"type": "integer"},
+                "measured": {"type": "integer"},
+                "filtered_out": {"type": "integer"}
+            }
+        }
+    })
+}
+
+/// Outcome of `apply_fixes`.
+pub fn fix_properties() -> Value {
+    json!({
+        "dry_run": {"type": "boolean"},
+        "applied": {
+            "type": "array",
+            "items": {"type": "string"},
+            "description": "Ids of the diagnostics whose fixes were applied"
+        },
+        "skipped": {
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "id": {"type": "string"},
+                    "reason": {"type": "string"}
+                }
+            }
+        },
+        "files": {
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "file": {"type": "string"},
+                    "diff": {"type": "string"}
+                }
+            }
+        },
+        "diff": {"type": "string", "description": "Unified diff of every change"}
+    })
+}
+
+/// Measurements and baseline comparison reported by `bench`.
+pub fn bench_properties() -> Value {
+    json!({
+        "benchmarks": {
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "name": {"type": "string"},
+                    "source": {"type": "string", "enum": ["libtest", "criterion"]},
+                    "ns_per_iter": {"type": "number"},
+                    "deviation_ns": {"type": "number"},
+                    "baseline_ns": {"type": "number"},
+                    "change_percent": {
+                        "type": "number",
+                        "description": "Change against the baseline, positive when slower"
+                    },
+                    "regression": {"type": "boolean"}
+                },
+                "required": ["name", "source", "ns_per_iter", "regression"]
+            }
+        },
+        "baseline": {"type": ["string", "null"]},
+        "regression_threshold": {"type": "number"},
+        "regression_count": {"type": "integer"}
+    })
+}
+
+/// Per-file line and function coverage reported by `coverage`.
+pub fn coverage_properties() -> Value {
+    let file_summary = json!({
+        "type": "object",
+        "properties": {
+            "file": {"type": "string"},
+            "lines_covered": {"type": "integer"},
+            "lines_total": {"type": "integer"},
+            "line_percent": {"type": "number"},
+            "functions_covered": {"type": "integer"},
+            "functions_total": {"type": "integer"},
+            "function_percent": {"type": "number"}
+        }
+    });
+    let mut properties = test_count_properties();
+    properties["totals"] = file_summary.clone();
+    properties["files"] = json!({
+        "type": "array",
+        "description": "Workspace source files, paths relative to the workspace root",
+        "items": file_summary
+    });
+    properties["uncovered"] = json!({
+        "type": "object",
+        "description": "Line ranges of the requested file that never ran",
+        "properties": {
+            "file": {"type": "string"},
+            "ranges": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "start": {"type": "integer"},
+                        "end": {"type": "integer"}
+                    }
+                }
+            }
+        }
+    });
+    properties
+}
+
+/// Per-test pass rates reported by `detect_flaky`.
+pub fn flaky_report_properties() -> Value {
+    json!({
+        "iterations": {"type": "integer"},
+        "shuffle": {"type": "boolean"},
+        "runs": {
+            "type": "array",
+            "description": "Totals of each iteration",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "iteration": {"type": "integer"},
+                    "test_threads": {"type": ["integer", "null"]},
+                    "passed": {"type": "integer"},
+                    "failed": {"type": "integer"}
+                }
+            }
+        },
+        "flaky_count": {"type": "integer"},
+        "always_failing_count": {"type": "integer"},
+        "test_results": {
+            "type": "array",
+            "description": "Flaky tests first, then tests that failed every run",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "name": {"type": "string"},
+                    "target": {"type": "string"},
+                    "runs": {"type": "integer"},
+                    "passed": {"type": "integer"},
+                    "failed": {"type": "integer"},
+                    "pass_ratio": {"type": "number"},
+                    "flaky": {"type": "boolean"},
+                    "failures": {
+                        "type": "array",
+                        "description": "Each distinct failure output with the iterations it occurred in",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "output": {"type": "string"},
+                                "iterations": {"type": "array", "items": {"type": "integer"}}
+                            }
+                        }
+                    }
+                },
+                "required": ["name", "runs", "passed", "failed", "pass_ratio", "flaky"]
+            }
+        }
+    })
+}
+
+/// Tests grouped by target, as reported by `list_tests`.
+pub fn test_list_properties() -> Value {
+    json!({
+        "targets": {
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "target": {"type": "string"},
+                    "binary": {"type": "string"},
+                    "tests": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "name": {"type": "string"},
+                                "kind": {"type": "string", "enum": ["test", "benchmark"]},
+                                "ignored": {"type": "boolean"}
+                            },
+                            "required": ["name", "kind", "ignored"]
+                        }
+                    }
+                },
+                "required": ["tests"]
+            }
+        },
+        "test_count": {"type": "integer"},
+        "ignored_count": {"type": "integer"}
+    })
+}
+
+/// Per-binary totals and per-test results reported by `structured` test runs.
+pub fn test_report_properties() -> Value {
+    let mut properties = test_count_properties();
+    properties["runner"] = json!({
+        "type": "string",
+        "enum": ["cargo", "nextest"],
+        "description": "Runner that executed the tests"
+    });
+    properties["suites"] = json!({
+        "type": "array",
+        "description": "Totals for each test binary, in the order they ran",
+        "items": {
+            "type": "object",
+            "properties": {
+                "target": {"type": "string"},
+                "binary": {"type": "string"},
+                "status": {"type": "string", "enum": ["ok", "failed"]},
+                "passed": {"type": "integer"},
+                "failed": {"type": "integer"},
+                "ignored": {"type": "integer"},
+                "measured": {"type": "integer"},
+                "filtered_out": {"type": "integer"},
+                "duration_secs": {"type": "number"}
+            }
+        }
+    });
+    properties["test_results"] = json!({
+        "type": "array",
+        "description": "One entry per test that ran or was ignored",
+        "items": {
+            "type": "object",
+            "properties": {
+                "name": {"type": "string"},
+                "target": {"type": "string"},
+                "status": {"type": "string", "enum": ["ok", "failed", "ignored"]},
+                "duration_secs": {"type": "number"},
+                "stdout": {
+                    "type": "string",
+                    "description": "Captured output, failed tests only"
+                },
+                "doctest": {
+                    "type": "object",
+                    "description": "Location of a doctest's code block",
+                    "properties": {
+                        "file": {"type": "string"},
+                        "item": {"type": "string"},
+                        "line": {"type": "integer"}
+                    },
+                    "required": ["file", "item", "line"]
+                }
+            },
+            "required": ["name", "status"]
+        }
+    });
+    properties
+}
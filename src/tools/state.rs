@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+use super::diagnostics::Diagnostic;
+use super::test_results::TestReport;
+use super::workspace_queue::workspace_key;
+
+/// Identifies the tests of one package (or of the whole workspace when no
+/// package was selected).
+type TestScope = (PathBuf, Option<String>);
+
+/// Results remembered between tool calls for the lifetime of the server.
+#[derive(Debug, Default)]
+pub struct ToolState {
+    /// Names of the tests that failed when they last ran, per scope.
+    failed_tests: Mutex<HashMap<TestScope, Vec<String>>>,
+    /// Diagnostics of the last compile or lint run per workspace.
+    diagnostics: Mutex<HashMap<PathBuf, RememberedDiagnostics>>,
+    next_diagnostic_id: AtomicU64,
+}
+
+/// Diagnostics of one run, with the time the run started. Their spans are
+/// only valid for files that have not changed since.
+#[derive(Debug, Clone)]
+pub struct RememberedDiagnostics {
+    pub recorded_at: SystemTime,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+fn test_scope(working_directory: Option<&str>, package: Option<&str>) -> TestScope {
+    (
+        workspace_key(working_directory),
+        package.map(str::to_string),
+    )
+}
+
+impl ToolState {
+    pub fn failed_tests(
+        &self,
+        working_directory: Option<&str>,
+        package: Option<&str>,
+    ) -> Vec<String> {
+        self.failed_tests
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(&test_scope(working_directory, package))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Remember the failures of a structured test run. Only a `complete`
+    /// run of every test in the scope replaces what is remembered; filtered
+    /// runs, reruns and runs that stopped at the first failing binary update
+    /// just the tests they ran.
+    pub fn record_test_run(
+        &self,
+        working_directory: Option<&str>,
+        package: Option<&str>,
+        report: &TestReport,
+        complete: bool,
+    ) {
+        let mut failed: Vec<String> = Vec::new();
+        for failure in report.failures() {
+            if !failed.contains(&failure.name) {
+                failed.push(failure.name.clone());
+            }
+        }
+        if complete {
+            self.record_failed_tests(working_directory, package, failed);
+        } else {
+            let passed: Vec<String> = report
+                .tests
+                .iter()
+                .filter(|test| test.status == "ok")
+                .map(|test| test.name.clone())
+                .collect();
+            self.merge_failed_tests(working_directory, package, &passed, failed);
+        }
+    }
+
+    /// Replace the remembered failures for a scope. An empty list forgets it.
+    fn record_failed_tests(
+        &self,
+        working_directory: Option<&str>,
+        package: Option<&str>,
+        names: Vec<String>,
+    ) {
+        let mut failed_tests = self
+            .failed_tests
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let scope = test_scope(working_directory, package);
+        if names.is_empty() {
+            failed_tests.remove(&scope);
+        } else {
+            failed_tests.insert(scope, names);
+        }
+    }
+
+    /// Update the remembered failures of a scope after a run that covered
+    /// only some of its tests: the ones that passed are forgotten and the
+    /// new failures added, while tests that did not run keep their state.
+    fn merge_failed_tests(
+        &self,
+        working_directory: Option<&str>,
+        package: Option<&str>,
+        passed: &[String],
+        failed: Vec<String>,
+    ) {
+        let mut failed_tests = self
+            .failed_tests
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let scope = test_scope(working_directory, package);
+        let mut names = failed_tests.remove(&scope).unwrap_or_default();
+        names.retain(|name| !passed.contains(name));
+        for name in failed {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        if !names.is_empty() {
+            failed_tests.insert(scope, names);
+        }
+    }
+
+    /// Give each diagnostic an id that is unique for the server's lifetime
+    /// and remember them as the workspace's latest diagnostics.
+    pub fn remember_diagnostics(
+        &self,
+        working_directory: Option<&str>,
+        diagnostics: &mut [Diagnostic],
+        recorded_at: SystemTime,
+    ) {
+        for diagnostic in diagnostics.iter_mut() {
+            let id = self.next_diagnostic_id.fetch_add(1, Ordering::Relaxed) + 1;
+            diagnostic.id = Some(format!("D{id}"));
+        }
+        self.diagnostics
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(
+                workspace_key(working_directory),
+                RememberedDiagnostics {
+                    recorded_at,
+                    diagnostics: diagnostics.to_vec(),
+                },
+            );
+    }
+
+    pub fn diagnostics(&self, working_directory: Option<&str>) -> Option<RememberedDiagnostics> {
+        self.diagnostics
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(&workspace_key(working_directory))
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::test_results::parse_test_report;
+
+    // `cargo test --no-fail-fast` on a crate with unit tests (one passing,
+    // one failing, one ignored), an integration test and a doctest.
+    const JSON_STDOUT: &str = include_str!("testdata/test_json.stdout");
+    const JSON_STDERR: &str = include_str!("testdata/test_json.stderr");
+
+    /// Libtest events of a run where each test has the given outcome.
+    fn run(outcomes: &[(&str, &str)]) -> TestReport {
+        let mut stdout = format!(
+            "{{ \"type\": \"suite\", \"event\": \"started\", \"test_count\": {} }}\n",
+            outcomes.len()
+        );
+        for (name, event) in outcomes {
+            stdout.push_str(&format!(
+                "{{ \"type\": \"test\", \"name\": \"{name}\", \"event\": \"{event}\" }}\n"
+            ));
+        }
+        let failed = outcomes.iter().filter(|(_, e)| *e == "failed").count();
+        stdout.push_str(&format!(
+            "{{ \"type\": \"suite\", \"event\": \"{}\", \"passed\": {}, \"failed\": {failed}, \"ignored\": 0, \"measured\": 0, \"filtered_out\": 0 }}\n",
+            if failed == 0 { "ok" } else { "failed" },
+            outcomes.len() - failed,
+        ));
+        parse_test_report(&stdout, "")
+    }
+
+    #[test]
+    fn complete_runs_replace_remembered_failures() {
+        let state = ToolState::default();
+        let wd = Some("/nonexistent/ws");
+        state.record_test_run(wd, None, &parse_test_report(JSON_STDOUT, JSON_STDERR), true);
+        assert_eq!(state.failed_tests(wd, None), ["tests::fails"]);
+        // Scopes are kept apart per package.
+        assert!(state.failed_tests(wd, Some("lt")).is_empty());
+
+        let report = run(&[("tests::adds", "failed"), ("tests::fails", "ok")]);
+        state.record_test_run(wd, None, &report, true);
+        assert_eq!(state.failed_tests(wd, None), ["tests::adds"]);
+
+        state.record_test_run(wd, None, &run(&[("tests::adds", "ok")]), true);
+        assert!(state.failed_tests(wd, None).is_empty());
+    }
+
+    #[test]
+    fn partial_runs_update_only_the_tests_they_ran() {
+        let state = ToolState::default();
+        let wd = Some("/nonexistent/ws");
+        let first = run(&[("alpha", "failed"), ("beta", "failed"), ("gamma", "ok")]);
+        state.record_test_run(wd, None, &first, true);
+        assert_eq!(state.failed_tests(wd, None), ["alpha", "beta"]);
+
+        // A rerun where alpha now passes and gamma newly fails.
+        let rerun = run(&[("alpha", "ok"), ("gamma", "failed")]);
+        state.record_test_run(wd, None, &rerun, false);
+        assert_eq!(state.failed_tests(wd, None), ["beta", "gamma"]);
+
+        // Failing again does not list a test twice.
+        state.record_test_run(wd, None, &run(&[("beta", "failed")]), false);
+        assert_eq!(state.failed_tests(wd, None), ["beta", "gamma"]);
+
+        state.record_test_run(wd, None, &run(&[("beta", "ok"), ("gamma", "ok")]), false);
+        assert!(state.failed_tests(wd, None).is_empty());
+    }
+}
@@ -0,0 +1,851 @@
+use serde::{Deserialize, Serialize};
+
+/// Totals reported by libtest's `test result:` lines.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TestCounts {
+    pub passed: u64,
+    pub failed: u64,
+    pub ignored: u64,
+    pub measured: u64,
+    pub filtered_out: u64,
+}
+
+/// Sum the `test result: ok. 2 passed; 0 failed; ...` summaries printed by
+/// each test binary. Returns `None` when no test binary ran.
+pub fn parse_test_counts(output: &str) -> Option<TestCounts> {
+    let mut counts: Option<TestCounts> = None;
+
+    for line in output.lines() {
+        let Some(rest) = line.trim().strip_prefix("test result: ") else {
+            continue;
+        };
+        let totals = counts.get_or_insert_with(TestCounts::default);
+        // "ok. 2 passed; 0 failed; 1 ignored; 0 measured; 0 filtered out; finished in 0.00s"
+        let rest = rest.split_once(". ").map_or(rest, |(_, rest)| rest);
+        for part in rest.split(';') {
+            let mut words = part.split_whitespace();
+            let (Some(number), Some(kind)) = (words.next(), words.next()) else {
+                continue;
+            };
+            let Ok(number) = number.parse::<u64>() else {
+                continue;
+            };
+            match kind {
+                "passed" => totals.passed += number,
+                "failed" => totals.failed += number,
+                "ignored" => totals.ignored += number,
+                "measured" => totals.measured += number,
+                "filtered" => totals.filtered_out += number,
+                _ => {}
+            }
+        }
+    }
+
+    counts
+}
+
+/// One event of libtest's `--format json` stream. Suite results carry the
+/// counts, test results the name, duration and captured output.
+#[derive(Debug, Deserialize)]
+struct LibtestEvent {
+    #[serde(rename = "type")]
+    kind: String,
+    event: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    exec_time: Option<f64>,
+    #[serde(default)]
+    stdout: Option<String>,
+    #[serde(flatten)]
+    counts: TestCounts,
+}
+
+/// Where a doctest's code block lives, parsed from names like
+/// `src/lib.rs - foo::Bar::baz (line 42)`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DoctestLocation {
+    pub file: String,
+    /// Path of the documented item, relative to the crate root.
+    pub item: String,
+    /// Line of the opening code fence.
+    pub line: u32,
+}
+
+/// Parse a rustdoc test name. Older toolchains append attributes, as in
+/// `src/lib.rs - foo (line 3) - compile fail`.
+pub fn parse_doctest_name(name: &str) -> Option<DoctestLocation> {
+    let (head, rest) = name.rsplit_once(" (line ")?;
+    let (line, _) = rest.split_once(')')?;
+    let (file, item) = head.split_once(" - ")?;
+    Some(DoctestLocation {
+        file: file.to_string(),
+        item: item.to_string(),
+        line: line.parse().ok()?,
+    })
+}
+
+/// Result of a single test.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestCase {
+    pub name: String,
+    /// Target the test belongs to, e.g. `unittests src/lib.rs`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// `ok`, `failed` or `ignored`.
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_secs: Option<f64>,
+    /// Captured output, kept for failures only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stdout: Option<String>,
+    /// Source location, for doctests.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub doctest: Option<DoctestLocation>,
+}
+
+/// Totals for one test binary.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestSuite {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub binary: Option<String>,
+    /// `ok` or `failed`.
+    pub status: String,
+    #[serde(flatten)]
+    pub counts: TestCounts,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_secs: Option<f64>,
+}
+
+/// Everything a JSON-format test run reported.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TestReport {
+    pub suites: Vec<TestSuite>,
+    pub tests: Vec<TestCase>,
+}
+
+impl TestReport {
+    /// Totals over every suite that finished.
+    pub fn counts(&self) -> TestCounts {
+        self.suites
+            .iter()
+            .fold(TestCounts::default(), |mut totals, suite| {
+                totals.passed += suite.counts.passed;
+                totals.failed += suite.counts.failed;
+                totals.ignored += suite.counts.ignored;
+                totals.measured += suite.counts.measured;
+                totals.filtered_out += suite.counts.filtered_out;
+                totals
+            })
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &TestCase> {
+        self.tests.iter().filter(|test| test.status == "failed")
+    }
+}
+
+/// The targets cargo announces on stderr before running each test binary,
+/// in order: `Running unittests src/lib.rs (target/debug/deps/foo-1234)` and
+/// `Doc-tests foo`. Returns (target, binary path) pairs.
+fn announced_targets(stderr: &str) -> Vec<(String, Option<String>)> {
+    stderr
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if let Some(rest) = line.strip_prefix("Running ") {
+                Some(match rest.rsplit_once(" (") {
+                    Some((target, binary)) => (
+                        target.to_string(),
+                        Some(binary.trim_end_matches(')').to_string()),
+                    ),
+                    None => (rest.to_string(), None),
+                })
+            } else {
+                line.strip_prefix("Doc-tests ")
+                    .map(|krate| (format!("doctests {krate}"), None))
+            }
+        })
+        .collect()
+}
+
+/// Parse the libtest JSON events on stdout into per-test results and
+/// per-binary totals. Suites are matched with the targets cargo printed on
+/// stderr by position, since both are emitted in the same order.
+pub fn parse_test_report(stdout: &str, stderr: &str) -> TestReport {
+    let mut targets = announced_targets(stderr).into_iter();
+    let mut current: (Option<String>, Option<String>) = (None, None);
+    let mut report = TestReport::default();
+
+    for line in stdout.lines() {
+        let line = line.trim();
+        if !line.starts_with('{') {
+            continue;
+        }
+        let Ok(event) = serde_json::from_str::<LibtestEvent>(line) else {
+            continue;
+        };
+
+        match (event.kind.as_str(), event.event.as_str()) {
+            ("suite", "started") => {
+                current = targets
+                    .next()
+                    .map_or((None, None), |(target, binary)| (Some(target), binary));
+            }
+            ("suite", status) => report.suites.push(TestSuite {
+                target: current.0.clone(),
+                binary: current.1.clone(),
+                status: status.to_string(),
+                counts: event.counts,
+                duration_secs: event.exec_time,
+            }),
+            ("test", status @ ("ok" | "failed" | "ignored")) => {
+                let Some(name) = event.name else {
+                    continue;
+                };
+                // nextest prefixes names with the binary id and prints no
+                // `Running` lines: `my-crate::api$tests::works`.
+                let name = match name.split_once('$') {
+                    Some((binary_id, name)) => {
+                        current.0.get_or_insert_with(|| binary_id.to_string());
+                        name.to_string()
+                    }
+                    None => name,
+                };
+                report.tests.push(TestCase {
+                    doctest: parse_doctest_name(&name),
+                    name,
+                    target: current.0.clone(),
+                    status: status.to_string(),
+                    duration_secs: event.exec_time,
+                    stdout: event.stdout.filter(|_| status == "failed"),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    report
+}
+
+/// A test or benchmark reported by a test binary in `--list` mode.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListedTest {
+    pub name: String,
+    /// `test` or `benchmark`.
+    pub kind: String,
+    pub ignored: bool,
+}
+
+/// The tests of one test binary, e.g. `unittests src/lib.rs` or
+/// `tests/api.rs`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestTarget {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub binary: Option<String>,
+    pub tests: Vec<ListedTest>,
+}
+
+/// Parse the output of `cargo test -- --list`. Each binary prints one
+/// `name: test` line per test followed by a `3 tests, 0 benchmarks`
+/// summary, which is what separates the targets.
+pub fn parse_test_list(stdout: &str, stderr: &str) -> Vec<TestTarget> {
+    let mut announced = announced_targets(stderr).into_iter();
+    let mut targets = Vec::new();
+    let mut tests = Vec::new();
+
+    for line in stdout.lines() {
+        if let Some((name, kind)) = line.rsplit_once(": ")
+            && (kind == "test" || kind == "benchmark")
+        {
+            tests.push(ListedTest {
+                name: name.to_string(),
+                kind: kind.to_string(),
+                ignored: false,
+            });
+        } else if line.ends_with(" benchmarks") || line.ends_with(" benchmark") {
+            let (target, binary) = announced
+                .next()
+                .map_or((None, None), |(target, binary)| (Some(target), binary));
+            targets.push(TestTarget {
+                target,
+                binary,
+                tests: std::mem::take(&mut tests),
+            });
+        }
+    }
+
+    targets
+}
+
+/// Flag the tests that also appear in the `--ignored` listing. A test is
+/// only ignored in the target it was listed under: unit tests and
+/// integration tests often share names such as `tests::it_works`.
+pub fn mark_ignored_tests(targets: &mut [TestTarget], ignored: &[TestTarget]) {
+    for target in targets {
+        let Some(ignored) = ignored.iter().find(|t| t.target == target.target) else {
+            continue;
+        };
+        for test in &mut target.tests {
+            test.ignored = ignored.tests.iter().any(|t| t.name == test.name);
+        }
+    }
+}
+
+/// One distinct way a test failed across repeated runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailureVariant {
+    pub output: String,
+    /// Iterations (1-based) in which the test failed this way.
+    pub iterations: Vec<u32>,
+}
+
+/// Outcomes of one test over repeated runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestHistory {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    pub runs: u32,
+    pub passed: u32,
+    pub failed: u32,
+    pub pass_ratio: f64,
+    /// Passed in some runs and failed in others.
+    pub flaky: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub failures: Vec<FailureVariant>,
+}
+
+/// Directory names that get a random suffix on every run: rustdoc's
+/// `rustdoctestXXXXXX` build directories and `tempfile`'s `.tmpXXXXXX`.
+const TEMP_DIR_PREFIXES: &[&str] = &["rustdoctest", ".tmp"];
+
+/// Captured output with the parts that change between otherwise identical
+/// failures removed: the thread id in `thread 'x' (1234) panicked` and the
+/// random suffix of temporary directories.
+fn failure_signature(output: &str) -> String {
+    output
+        .lines()
+        .map(|line| {
+            let line = if line.starts_with("thread '")
+                && let Some(start) = line.find("' (")
+                && let Some(len) = line[start + 3..].find(')')
+                && line[start + 3..start + 3 + len]
+                    .chars()
+                    .all(|c| c.is_ascii_digit())
+            {
+                format!("{}'{}", &line[..start], &line[start + 4 + len..])
+            } else {
+                line.to_string()
+            };
+            normalize_temp_dirs(&line)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Replace the random suffix of path components starting with one of
+/// `TEMP_DIR_PREFIXES` with `*`.
+fn normalize_temp_dirs(line: &str) -> String {
+    let mut normalized = String::with_capacity(line.len());
+    let mut rest = line;
+    'components: while let Some(separator) = rest.find(['/', '\\']) {
+        normalized.push_str(&rest[..=separator]);
+        rest = &rest[separator + 1..];
+        for prefix in TEMP_DIR_PREFIXES {
+            if let Some(suffix) = rest.strip_prefix(prefix) {
+                let len = suffix
+                    .find(|c: char| !c.is_ascii_alphanumeric())
+                    .unwrap_or(suffix.len());
+                if len > 0 {
+                    normalized.push_str(prefix);
+                    normalized.push('*');
+                    rest = &suffix[len..];
+                    continue 'components;
+                }
+            }
+        }
+    }
+    normalized.push_str(rest);
+    normalized
+}
+
+/// Accumulates structured test reports from repeated runs of the same tests.
+#[derive(Debug, Default)]
+pub struct FlakyTracker {
+    tests: Vec<TestHistory>,
+}
+
+impl FlakyTracker {
+    pub fn record(&mut self, iteration: u32, report: &TestReport) {
+        for test in &report.tests {
+            if test.status == "ignored" {
+                continue;
+            }
+            let index = match self
+                .tests
+                .iter()
+                .position(|t| t.name == test.name && t.target == test.target)
+            {
+                Some(index) => index,
+                None => {
+                    self.tests.push(TestHistory {
+                        name: test.name.clone(),
+                        target: test.target.clone(),
+                        runs: 0,
+                        passed: 0,
+                        failed: 0,
+                        pass_ratio: 0.0,
+                        flaky: false,
+                        failures: Vec::new(),
+                    });
+                    self.tests.len() - 1
+                }
+            };
+            let history = &mut self.tests[index];
+
+            history.runs += 1;
+            if test.status == "ok" {
+                history.passed += 1;
+            } else {
+                history.failed += 1;
+                let output = failure_signature(test.stdout.as_deref().unwrap_or_default());
+                match history.failures.iter_mut().find(|f| f.output == output) {
+                    Some(variant) => variant.iterations.push(iteration),
+                    None => history.failures.push(FailureVariant {
+                        output,
+                        iterations: vec![iteration],
+                    }),
+                }
+            }
+            history.pass_ratio = f64::from(history.passed) / f64::from(history.runs);
+            history.flaky = history.passed > 0 && history.failed > 0;
+        }
+    }
+
+    /// Flaky tests first, then tests that always failed, then the rest.
+    pub fn finish(mut self) -> Vec<TestHistory> {
+        self.tests
+            .sort_by_key(|t| (!t.flaky, t.failed == 0, t.name.clone()));
+        self.tests
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `cargo test --no-fail-fast` on a crate with unit tests (one passing,
+    // one failing, one ignored), an integration test and a doctest.
+    const JSON_STDOUT: &str = include_str!("testdata/test_json.stdout");
+    const JSON_STDERR: &str = include_str!("testdata/test_json.stderr");
+    const PLAIN_STDOUT: &str = include_str!("testdata/test_plain.stdout");
+    // The same crate's tests, minus the doctest, in the layout of
+    // `cargo nextest run --message-format libtest-json`.
+    const NEXTEST_STDOUT: &str = include_str!("testdata/nextest_libtest.stdout");
+    const NEXTEST_STDERR: &str = include_str!("testdata/nextest_libtest.stderr");
+
+    #[test]
+    fn sums_plain_test_results() {
+        let counts = parse_test_counts(PLAIN_STDOUT).unwrap();
+        assert_eq!(
+            (
+                counts.passed,
+                counts.failed,
+                counts.ignored,
+                counts.filtered_out
+            ),
+            (3, 1, 1, 0)
+        );
+        assert!(parse_test_counts("error: could not compile `lt`").is_none());
+    }
+
+    #[test]
+    fn matches_json_suites_with_announced_targets() {
+        let report = parse_test_report(JSON_STDOUT, JSON_STDERR);
+        let suites: Vec<(Option<&str>, Option<&str>, &str)> = report
+            .suites
+            .iter()
+            .map(|suite| {
+                (
+                    suite.target.as_deref(),
+                    suite.binary.as_deref(),
+                    suite.status.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            suites,
+            [
+                (
+                    Some("unittests src/lib.rs"),
+                    Some("target/debug/deps/lt-d08e8b453f7c5fff"),
+                    "failed"
+                ),
+                (
+                    Some("tests/api.rs"),
+                    Some("target/debug/deps/api-93163d3e9c39e42c"),
+                    "ok"
+                ),
+                (Some("doctests lt"), None, "ok"),
+            ]
+        );
+
+        let counts = report.counts();
+        assert_eq!((counts.passed, counts.failed, counts.ignored), (3, 1, 1));
+    }
+
+    #[test]
+    fn parses_json_test_events() {
+        let report = parse_test_report(JSON_STDOUT, JSON_STDERR);
+        let tests: Vec<(&str, Option<&str>, &str)> = report
+            .tests
+            .iter()
+            .map(|test| {
+                (
+                    test.name.as_str(),
+                    test.target.as_deref(),
+                    test.status.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            tests,
+            [
+                ("tests::adds", Some("unittests src/lib.rs"), "ok"),
+                ("tests::fails", Some("unittests src/lib.rs"), "failed"),
+                ("tests::slow", Some("unittests src/lib.rs"), "ignored"),
+                ("api_works", Some("tests/api.rs"), "ok"),
+                ("src/lib.rs - add (line 1)", Some("doctests lt"), "ok"),
+            ]
+        );
+
+        // Output is only kept for failures.
+        let [failure] = report.failures().collect::<Vec<_>>()[..] else {
+            panic!("expected one failure");
+        };
+        assert!(
+            failure
+                .stdout
+                .as_deref()
+                .unwrap()
+                .contains("assertion `left == right` failed: wrong sum")
+        );
+        assert_eq!(failure.duration_secs, Some(0.013402942));
+        assert!(
+            report
+                .tests
+                .iter()
+                .all(|test| test.status == "failed" || test.stdout.is_none())
+        );
+    }
+
+    #[test]
+    fn parses_doctest_names() {
+        assert_eq!(
+            parse_doctest_name("src/util/mod.rs - util::helper (line 1)"),
+            Some(DoctestLocation {
+                file: "src/util/mod.rs".to_string(),
+                item: "util::helper".to_string(),
+                line: 1,
+            })
+        );
+        assert_eq!(
+            parse_doctest_name("src/lib.rs - Counter::bump (line 9) - compile fail"),
+            Some(DoctestLocation {
+                file: "src/lib.rs".to_string(),
+                item: "Counter::bump".to_string(),
+                line: 9,
+            })
+        );
+        assert_eq!(parse_doctest_name("tests::adds"), None);
+    }
+
+    #[test]
+    fn splits_test_lists_by_target() {
+        let targets = parse_test_list(
+            include_str!("testdata/test_list.stdout"),
+            include_str!("testdata/test_list.stderr"),
+        );
+        let listed: Vec<(Option<&str>, Vec<&str>)> = targets
+            .iter()
+            .map(|target| {
+                (
+                    target.target.as_deref(),
+                    target.tests.iter().map(|test| test.name.as_str()).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            listed,
+            [
+                (
+                    Some("unittests src/lib.rs"),
+                    vec!["tests::adds", "tests::fails", "tests::slow"]
+                ),
+                (Some("tests/api.rs"), vec!["api_works"]),
+                (Some("doctests lt"), vec!["src/lib.rs - add (line 1)"]),
+            ]
+        );
+        assert!(
+            targets
+                .iter()
+                .flat_map(|target| &target.tests)
+                .all(|test| test.kind == "test" && !test.ignored)
+        );
+
+        // With `--ignored`, binaries without ignored tests still print a
+        // summary, which keeps the targets aligned.
+        let ignored = parse_test_list(
+            include_str!("testdata/test_list_ignored.stdout"),
+            include_str!("testdata/test_list.stderr"),
+        );
+        let counts: Vec<usize> = ignored.iter().map(|target| target.tests.len()).collect();
+        assert_eq!(counts, [1, 0, 0]);
+        assert_eq!(ignored[0].tests[0].name, "tests::slow");
+    }
+
+    #[test]
+    fn marks_ignored_tests_per_target() {
+        let stderr = include_str!("testdata/test_list.stderr");
+        let mut targets = parse_test_list(include_str!("testdata/test_list.stdout"), stderr);
+        let ignored = parse_test_list(include_str!("testdata/test_list_ignored.stdout"), stderr);
+        mark_ignored_tests(&mut targets, &ignored);
+        let marked: Vec<&str> = targets
+            .iter()
+            .flat_map(|target| &target.tests)
+            .filter(|test| test.ignored)
+            .map(|test| test.name.as_str())
+            .collect();
+        assert_eq!(marked, ["tests::slow"]);
+
+        // `tests::it_works` in the library and in tests/api.rs, ignored
+        // only in the latter.
+        let mut targets = parse_test_list(
+            "tests::it_works: test\n\n1 test, 0 benchmarks\n\
+             tests::it_works: test\n\n1 test, 0 benchmarks\n\
+             0 tests, 0 benchmarks\n",
+            stderr,
+        );
+        let ignored = parse_test_list(
+            "0 tests, 0 benchmarks\n\
+             tests::it_works: test\n\n1 test, 0 benchmarks\n\
+             0 tests, 0 benchmarks\n",
+            stderr,
+        );
+        mark_ignored_tests(&mut targets, &ignored);
+        let marked: Vec<(Option<&str>, bool)> = targets
+            .iter()
+            .flat_map(|target| {
+                target
+                    .tests
+                    .iter()
+                    .map(|test| (target.target.as_deref(), test.ignored))
+            })
+            .collect();
+        assert_eq!(
+            marked,
+            [
+                (Some("unittests src/lib.rs"), false),
+                (Some("tests/api.rs"), true)
+            ]
+        );
+    }
+
+    #[test]
+    fn failure_signature_ignores_thread_ids_and_temp_dirs() {
+        let first = "Test executable failed (exit status: 101).\n\nstderr:\n\nthread 'main' (6322) panicked at /tmp/rustdoctestFoBKXt/doctest_bundle_2024.rs:6:1:\nassertion `left == right` failed\n  left: 2\n right: 3\n";
+        let second = "Test executable failed (exit status: 101).\n\nstderr:\n\nthread 'main' (6353) panicked at /tmp/rustdoctest8To9Rm/doctest_bundle_2024.rs:6:1:\nassertion `left == right` failed\n  left: 2\n right: 3\n";
+        assert_eq!(failure_signature(first), failure_signature(second));
+        assert!(
+            failure_signature(first).contains(
+                "thread 'main' panicked at /tmp/rustdoctest*/doctest_bundle_2024.rs:6:1:"
+            )
+        );
+
+        let first =
+            "thread 'io' (17) panicked at src/lib.rs:9:5:\nmissing C:\\Temp\\.tmpA1b2C3\\data.txt";
+        let second =
+            "thread 'io' (42) panicked at src/lib.rs:9:5:\nmissing C:\\Temp\\.tmpZz9Yy8\\data.txt";
+        assert_eq!(failure_signature(first), failure_signature(second));
+    }
+
+    #[test]
+    fn failure_signature_keeps_different_failures_apart() {
+        assert_ne!(
+            failure_signature("thread 'a' (1) panicked at src/lib.rs:3:5:\n  left: 2\n right: 3"),
+            failure_signature("thread 'a' (1) panicked at src/lib.rs:3:5:\n  left: 1\n right: 3")
+        );
+    }
+
+    #[test]
+    fn splits_nextest_binary_ids_from_test_names() {
+        let report = parse_test_report(NEXTEST_STDOUT, NEXTEST_STDERR);
+        let tests: Vec<(&str, Option<&str>, &str)> = report
+            .tests
+            .iter()
+            .map(|test| {
+                (
+                    test.name.as_str(),
+                    test.target.as_deref(),
+                    test.status.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            tests,
+            [
+                ("tests::slow", Some("lt"), "ignored"),
+                ("tests::adds", Some("lt"), "ok"),
+                ("tests::fails", Some("lt"), "failed"),
+                ("api_works", Some("lt::api"), "ok"),
+            ]
+        );
+        assert!(report.tests.iter().all(|test| test.doctest.is_none()));
+        assert!(
+            report.tests[2]
+                .stdout
+                .as_deref()
+                .is_some_and(|stdout| stdout.contains("wrong sum"))
+        );
+
+        let suites: Vec<(Option<&str>, &str)> = report
+            .suites
+            .iter()
+            .map(|suite| (suite.target.as_deref(), suite.status.as_str()))
+            .collect();
+        assert_eq!(suites, [(Some("lt"), "failed"), (Some("lt::api"), "ok")]);
+        let counts = report.counts();
+        assert_eq!((counts.passed, counts.failed, counts.ignored), (2, 1, 1));
+    }
+
+    /// A run of tests given as `(target, name, status, output)`.
+    fn flaky_run(tests: &[(&str, &str, &str, &str)]) -> TestReport {
+        TestReport {
+            suites: Vec::new(),
+            tests: tests
+                .iter()
+                .map(|(target, name, status, output)| TestCase {
+                    name: name.to_string(),
+                    target: Some(target.to_string()),
+                    status: status.to_string(),
+                    duration_secs: None,
+                    stdout: (*status == "failed").then(|| output.to_string()),
+                    doctest: None,
+                })
+                .collect(),
+        }
+    }
+
+    const TIMEOUT: &str =
+        "thread 'tests::racy' (101) panicked at src/lib.rs:20:9:\ntimed out waiting for the lock\n";
+    const WRONG_SUM: &str =
+        "thread 'tests::racy' (202) panicked at src/lib.rs:24:5:\nassertion failed: sum == 4\n";
+
+    #[test]
+    fn classifies_tests_across_runs() {
+        let lib = "unittests src/lib.rs";
+        let mut tracker = FlakyTracker::default();
+        tracker.record(
+            1,
+            &flaky_run(&[
+                (lib, "tests::racy", "ok", ""),
+                (lib, "tests::broken", "failed", WRONG_SUM),
+                (lib, "tests::stable", "ok", ""),
+                (lib, "tests::slow", "ignored", ""),
+            ]),
+        );
+        tracker.record(
+            2,
+            &flaky_run(&[
+                (lib, "tests::racy", "failed", TIMEOUT),
+                (lib, "tests::broken", "failed", WRONG_SUM),
+                (lib, "tests::stable", "ok", ""),
+            ]),
+        );
+        tracker.record(
+            3,
+            &flaky_run(&[
+                (lib, "tests::racy", "failed", WRONG_SUM),
+                (lib, "tests::broken", "failed", WRONG_SUM),
+                (lib, "tests::stable", "ok", ""),
+            ]),
+        );
+        tracker.record(
+            4,
+            &flaky_run(&[
+                (
+                    lib,
+                    "tests::racy",
+                    "failed",
+                    &TIMEOUT.replace("(101)", "(303)"),
+                ),
+                (lib, "tests::broken", "failed", WRONG_SUM),
+                (lib, "tests::stable", "ok", ""),
+            ]),
+        );
+
+        let histories = tracker.finish();
+        let summary: Vec<(&str, u32, u32, u32, bool)> = histories
+            .iter()
+            .map(|h| (h.name.as_str(), h.runs, h.passed, h.failed, h.flaky))
+            .collect();
+        // Flaky first, then consistent failures, then passing tests; ignored
+        // runs are not counted.
+        assert_eq!(
+            summary,
+            [
+                ("tests::racy", 4, 1, 3, true),
+                ("tests::broken", 4, 0, 4, false),
+                ("tests::stable", 4, 4, 0, false),
+            ]
+        );
+        assert_eq!(histories[0].pass_ratio, 0.25);
+        assert_eq!(histories[1].pass_ratio, 0.0);
+
+        // The racy test failed two different ways; the timeouts only differ
+        // in the thread id.
+        let variants: Vec<&[u32]> = histories[0]
+            .failures
+            .iter()
+            .map(|variant| variant.iterations.as_slice())
+            .collect();
+        assert_eq!(variants, [&[2, 4][..], &[3]]);
+        assert!(histories[0].failures[0].output.contains("timed out"));
+        assert_eq!(histories[1].failures.len(), 1);
+        assert_eq!(histories[1].failures[0].iterations, [1, 2, 3, 4]);
+        assert!(histories[2].failures.is_empty());
+    }
+
+    #[test]
+    fn tracks_tests_of_the_same_name_per_target() {
+        let mut tracker = FlakyTracker::default();
+        for iteration in 1..=2 {
+            let status = if iteration == 1 { "failed" } else { "ok" };
+            tracker.record(
+                iteration,
+                &flaky_run(&[
+                    ("unittests src/lib.rs", "it_works", "ok", ""),
+                    ("tests/api.rs", "it_works", status, WRONG_SUM),
+                ]),
+            );
+        }
+
+        let histories = tracker.finish();
+        let summary: Vec<(Option<&str>, bool)> = histories
+            .iter()
+            .map(|h| (h.target.as_deref(), h.flaky))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (Some("tests/api.rs"), true),
+                (Some("unittests src/lib.rs"), false)
+            ]
+        );
+    }
+}
@@ -1,179 +1,608 @@
-use crate::types::Tool;
+use serde_json::json;
+
+use super::cargo_tools::{common_properties, object_schema, working_directory_property};
+use super::schemas::{
+    audit_properties, coverage_properties, dependency_graph_properties, diagnostics_properties,
+    duplicates_properties, explanation_properties, fix_properties, flaky_report_properties,
+    lint_properties, outdated_properties, output_schema, test_list_properties,
+    test_report_properties, workspace_properties,
+};
+use crate::types::{Tool, ToolAnnotations};
 
 pub fn get_workflow_tools() -> Vec<Tool> {
     vec![
         Tool {
             name: "compile".to_string(),
             description: "Check if the code compiles without producing binaries. Fast feedback for development.".to_string(),
-            input_schema: serde_json::json!({
-                "type": "object",
-                "properties": {
-                    "working_directory": {
-                        "type": "string",
-                        "description": "Working directory to run cargo command in"
-                    },
-                    "package": {
-                        "type": "string", 
-                        "description": "Package to check (equivalent to -p flag)"
-                    },
-                    "no_tests": {
-                        "type": "boolean",
-                        "description": "Disable test checking (tests are checked by default)"
-                    }
-                }
-            }),
+            input_schema: object_schema(
+                &[
+                    common_properties(),
+                    json!({
+                        "package": {
+                            "type": "string",
+                            "description": "Package to check (equivalent to -p flag)"
+                        },
+                        "no_tests": {
+                            "type": "boolean",
+                            "description": "Disable test checking (tests are checked by default)"
+                        },
+                        "explain_errors": {
+                            "type": "boolean",
+                            "description": "Include the rustc --explain text for each distinct error code (default: false)"
+                        }
+                    }),
+                ],
+                &[],
+            ),
+            output_schema: output_schema(diagnostics_properties()),
+            annotations: ToolAnnotations::read_only(false),
         },
         Tool {
             name: "lint".to_string(),
-            description: "Lint code with pedantic checks, options to soften to warnings and ignore missing docs".to_string(),
-            input_schema: serde_json::json!({
-                "type": "object",
-                "properties": {
-                    "working_directory": {
-                        "type": "string",
-                        "description": "Working directory to run cargo command in"
-                    },
-                    "package": {
-                        "type": "string", 
-                        "description": "Package to lint (equivalent to -p flag)"
-                    },
-                    "no_tests": {
-                        "type": "boolean",
-                        "description": "Disable test linting (tests are linted by default)"
-                    },
-                    "warn_only": {
-                        "type": "boolean",
-                        "description": "Use warnings instead of errors for pedantic checks"
-                    },
-                    "ignore_docs": {
-                        "type": "boolean",
-                        "description": "Ignore missing documentation warnings"
-                    }
-                }
-            }),
+            description: "Lint code with clippy. Denies the pedantic group by default; choose lint groups, allow/warn/deny individual lints, or follow only the workspace's [lints] configuration. Reports the lint levels in effect".to_string(),
+            input_schema: object_schema(
+                &[
+                    common_properties(),
+                    json!({
+                        "package": {
+                            "type": "string",
+                            "description": "Package to lint (equivalent to -p flag)"
+                        },
+                        "no_tests": {
+                            "type": "boolean",
+                            "description": "Disable test linting (tests are linted by default)"
+                        },
+                        "warn_only": {
+                            "type": "boolean",
+                            "description": "Use warnings instead of errors for the selected lint groups"
+                        },
+                        "ignore_docs": {
+                            "type": "boolean",
+                            "description": "Ignore missing documentation warnings"
+                        },
+                        "lint_groups": {
+                            "type": "array",
+                            "items": {
+                                "type": "string",
+                                "enum": ["all", "pedantic", "nursery", "cargo", "restriction"]
+                            },
+                            "description": "Clippy lint groups to enable (default: [\"pedantic\"])"
+                        },
+                        "allow": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "description": "Lints to allow, e.g. clippy::module_name_repetitions"
+                        },
+                        "warn": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "description": "Lints to warn on"
+                        },
+                        "deny": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "description": "Lints to deny, e.g. clippy::unwrap_used"
+                        },
+                        "workspace_lints_only": {
+                            "type": "boolean",
+                            "description": "Pass no lint flags and use only the [lints] tables of the manifests"
+                        }
+                    }),
+                ],
+                &[],
+            ),
+            output_schema: output_schema(lint_properties()),
+            annotations: ToolAnnotations::read_only(false),
         },
         Tool {
             name: "clean".to_string(),
             description: "Clean build artifacts".to_string(),
-            input_schema: serde_json::json!({
-                "type": "object",
-                "properties": {
-                    "working_directory": {
-                        "type": "string",
-                        "description": "Working directory to run cargo command in"
-                    }
-                }
-            }),
+            input_schema: object_schema(
+                &[
+                    common_properties(),
+                ],
+                &[],
+            ),
+            output_schema: output_schema(json!({})),
+            annotations: ToolAnnotations::mutating(true, true, false),
         },
         Tool {
             name: "search_crates".to_string(),
             description: "Search for crates on crates.io".to_string(),
-            input_schema: serde_json::json!({
-                "type": "object",
-                "properties": {
-                    "working_directory": {
-                        "type": "string",
-                        "description": "Working directory to run cargo command in"
-                    },
-                    "query": {
-                        "type": "string",
-                        "description": "Search query for crate names"
-                    },
-                    "limit": {
-                        "type": "integer",
-                        "description": "Maximum number of results (default: 10)"
-                    }
-                },
-                "required": ["query"]
-            }),
+            input_schema: object_schema(
+                &[
+                    common_properties(),
+                    json!({
+                        "query": {
+                            "type": "string",
+                            "description": "Search query for crate names"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Maximum number of results (default: 10)"
+                        }
+                    }),
+                ],
+                &["query"],
+            ),
+            output_schema: output_schema(json!({})),
+            annotations: ToolAnnotations::read_only(true),
         },
         Tool {
             name: "crate_info".to_string(),
             description: "Get detailed information about a crate including features".to_string(),
-            input_schema: serde_json::json!({
-                "type": "object",
-                "properties": {
-                    "working_directory": {
-                        "type": "string",
-                        "description": "Working directory to run cargo command in"
-                    },
-                    "crate_name": {
-                        "type": "string",
-                        "description": "Name of the crate to get information about"
-                    }
-                },
-                "required": ["crate_name"]
-            }),
+            input_schema: object_schema(
+                &[
+                    common_properties(),
+                    json!({
+                        "crate_name": {
+                            "type": "string",
+                            "description": "Name of the crate to get information about"
+                        }
+                    }),
+                ],
+                &["crate_name"],
+            ),
+            output_schema: output_schema(json!({})),
+            annotations: ToolAnnotations::read_only(true),
         },
         Tool {
             name: "add_crate".to_string(),
             description: "Add a crate dependency with optional features".to_string(),
-            input_schema: serde_json::json!({
-                "type": "object",
-                "properties": {
-                    "working_directory": {
-                        "type": "string",
-                        "description": "Working directory to run cargo command in"
-                    },
-                    "package": {
-                        "type": "string",
-                        "description": "Package to add dependency to"
-                    },
-                    "crate_name": {
-                        "type": "string",
-                        "description": "Name of the crate to add"
-                    },
-                    "features": {
-                        "type": "array",
-                        "items": {"type": "string"},
-                        "description": "Features to enable for this crate"
-                    }
-                },
-                "required": ["crate_name"]
-            }),
+            input_schema: object_schema(
+                &[
+                    common_properties(),
+                    json!({
+                        "package": {
+                            "type": "string",
+                            "description": "Package to add dependency to"
+                        },
+                        "crate_name": {
+                            "type": "string",
+                            "description": "Name of the crate to add"
+                        },
+                        "features": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "description": "Features to enable for this crate"
+                        }
+                    }),
+                ],
+                &["crate_name"],
+            ),
+            output_schema: output_schema(json!({})),
+            annotations: ToolAnnotations::mutating(false, true, true),
         },
         Tool {
             name: "remove_crate".to_string(),
             description: "Remove a crate dependency".to_string(),
-            input_schema: serde_json::json!({
-                "type": "object",
-                "properties": {
-                    "working_directory": {
-                        "type": "string",
-                        "description": "Working directory to run cargo command in"
-                    },
-                    "package": {
-                        "type": "string",
-                        "description": "Package to remove dependency from"
-                    },
-                    "crate_name": {
-                        "type": "string",
-                        "description": "Name of the crate to remove"
-                    }
-                },
-                "required": ["crate_name"]
-            }),
+            input_schema: object_schema(
+                &[
+                    common_properties(),
+                    json!({
+                        "package": {
+                            "type": "string",
+                            "description": "Package to remove dependency from"
+                        },
+                        "crate_name": {
+                            "type": "string",
+                            "description": "Name of the crate to remove"
+                        }
+                    }),
+                ],
+                &["crate_name"],
+            ),
+            output_schema: output_schema(json!({})),
+            annotations: ToolAnnotations::mutating(true, true, false),
         },
         Tool {
             name: "test".to_string(),
             description: "Run tests with optional filtering".to_string(),
-            input_schema: serde_json::json!({
-                "type": "object",
-                "properties": {
-                    "working_directory": {
-                        "type": "string",
-                        "description": "Working directory to run cargo command in"
-                    },
-                    "package": {
-                        "type": "string",
-                        "description": "Package to test"
-                    },
-                    "test_name": {
-                        "type": "string",
-                        "description": "Specific test name to run"
-                    }
-                }
-            }),
+            input_schema: object_schema(
+                &[
+                    common_properties(),
+                    json!({
+                        "package": {
+                            "type": "string",
+                            "description": "Package to test"
+                        },
+                        "test_name": {
+                            "type": "string",
+                            "description": "Specific test name to run"
+                        },
+                        "exact": {
+                            "type": "boolean",
+                            "description": "Match test_name exactly instead of as a substring"
+                        },
+                        "rerun_failed": {
+                            "type": "boolean",
+                            "description": "Rerun only the tests that failed the last time they ran in a structured run for this workspace and package, instead of test_name. Implies structured"
+                        },
+                        "doc": {
+                            "type": "boolean",
+                            "description": "Only run doctests (--doc); results carry the file, item and line of each code block. Implies structured"
+                        },
+                        "no_fail_fast": {
+                            "type": "boolean",
+                            "description": "Run every test binary even after one of them fails"
+                        },
+                        "runner": {
+                            "type": "string",
+                            "enum": ["cargo", "nextest"],
+                            "description": "Run through cargo test or cargo nextest run (defaults to the server setting). nextest results are always structured; falls back to cargo test when nextest is not installed"
+                        },
+                        "structured": {
+                            "type": "boolean",
+                            "description": "Run the test harness with JSON event output and return per-test results and per-binary totals (default: false)"
+                        }
+                    }),
+                ],
+                &[],
+            ),
+            output_schema: output_schema(test_report_properties()),
+            annotations: ToolAnnotations::mutating(false, true, false),
+        },
+        Tool {
+            name: "list_tests".to_string(),
+            description: "List the tests of a package without running them, grouped by target, with their ignored status".to_string(),
+            input_schema: object_schema(
+                &[
+                    common_properties(),
+                    json!({
+                        "package": {
+                            "type": "string",
+                            "description": "Package whose tests to list"
+                        },
+                        "test_name": {
+                            "type": "string",
+                            "description": "Only list tests whose name contains this string"
+                        },
+                        "lib": {
+                            "type": "boolean",
+                            "description": "Only the library's unit tests"
+                        },
+                        "bin": {
+                            "type": "string",
+                            "description": "Only the specified binary"
+                        },
+                        "test": {
+                            "type": "string",
+                            "description": "Only the specified integration test target"
+                        },
+                        "tests": {
+                            "type": "boolean",
+                            "description": "All test targets"
+                        },
+                        "all_targets": {
+                            "type": "boolean",
+                            "description": "All targets, including benches and examples"
+                        },
+                        "workspace": {
+                            "type": "boolean",
+                            "description": "List tests of every workspace member"
+                        }
+                    }),
+                ],
+                &[],
+            ),
+            output_schema: output_schema(test_list_properties()),
+            annotations: ToolAnnotations::read_only(false),
+        },
+        Tool {
+            name: "detect_flaky".to_string(),
+            description: "Run tests repeatedly to find flaky ones, reporting each test's pass ratio and every distinct failure output. Every iteration is a separate cargo run with its own timeout".to_string(),
+            input_schema: object_schema(
+                &[
+                    common_properties(),
+                    json!({
+                        "package": {
+                            "type": "string",
+                            "description": "Package to test"
+                        },
+                        "test_name": {
+                            "type": "string",
+                            "description": "Only run tests whose name contains this string"
+                        },
+                        "exact": {
+                            "type": "boolean",
+                            "description": "Match test_name exactly instead of as a substring"
+                        },
+                        "iterations": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "description": "Number of times to run the tests (default: 10)"
+                        },
+                        "shuffle": {
+                            "type": "boolean",
+                            "description": "Run tests in a random order in every iteration"
+                        },
+                        "thread_counts": {
+                            "type": "array",
+                            "items": {"type": "integer", "minimum": 1},
+                            "description": "Test thread counts to cycle through, one per iteration (e.g. [1, 4, 16])"
+                        }
+                    }),
+                ],
+                &[],
+            ),
+            output_schema: output_schema(flaky_report_properties()),
+            annotations: ToolAnnotations::read_only(false),
+        },
+        Tool {
+            name: "coverage".to_string(),
+            description: "Measure line and function coverage of the tests with -C instrument-coverage and llvm-cov. Requires the llvm-tools rustup component".to_string(),
+            input_schema: object_schema(
+                &[
+                    common_properties(),
+                    json!({
+                        "package": {
+                            "type": "string",
+                            "description": "Package to measure"
+                        },
+                        "workspace": {
+                            "type": "boolean",
+                            "description": "Measure every workspace member"
+                        },
+                        "features": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "description": "Features to activate"
+                        },
+                        "all_features": {
+                            "type": "boolean",
+                            "description": "Activate all features"
+                        },
+                        "no_default_features": {
+                            "type": "boolean",
+                            "description": "Do not activate the default features"
+                        },
+                        "lib": {
+                            "type": "boolean",
+                            "description": "Only run the library's unit tests"
+                        },
+                        "tests": {
+                            "type": "boolean",
+                            "description": "Only run test targets"
+                        },
+                        "test": {
+                            "type": "string",
+                            "description": "Only run the specified integration test target"
+                        },
+                        "file": {
+                            "type": "string",
+                            "description": "Source file, relative to the workspace root, to return uncovered line ranges for"
+                        }
+                    }),
+                ],
+                &[],
+            ),
+            output_schema: output_schema(coverage_properties()),
+            annotations: ToolAnnotations::read_only(false),
+        },
+        Tool {
+            name: "apply_fixes".to_string(),
+            description: "Apply the machine-applicable suggestions of selected diagnostics from the last compile or lint call, returning a unified diff".to_string(),
+            input_schema: object_schema(
+                &[
+                    working_directory_property(),
+                    json!({
+                        "diagnostic_ids": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "description": "Ids of the diagnostics to fix, as returned by compile or lint"
+                        },
+                        "dry_run": {
+                            "type": "boolean",
+                            "description": "Only return the diff without changing any file"
+                        }
+                    }),
+                ],
+                &["diagnostic_ids"],
+            ),
+            output_schema: output_schema(fix_properties()),
+            annotations: ToolAnnotations::mutating(true, false, false),
+        },
+        Tool {
+            name: "explain_error".to_string(),
+            description: "Explain a compiler error code such as E0502 using rustc --explain".to_string(),
+            input_schema: object_schema(
+                &[
+                    common_properties(),
+                    json!({
+                        "code": {
+                            "type": "string",
+                            "description": "Error code, e.g. E0502"
+                        }
+                    }),
+                ],
+                &["code"],
+            ),
+            output_schema: output_schema(explanation_properties()),
+            annotations: ToolAnnotations::read_only(false),
+        },
+        Tool {
+            name: "workspace_info".to_string(),
+            description: "Describe the workspace layout: members, their targets with source paths, features, declared dependencies, edition and rust-version".to_string(),
+            input_schema: object_schema(
+                &[
+                    common_properties(),
+                    json!({
+                        "package": {
+                            "type": "string",
+                            "description": "Only describe this workspace member"
+                        }
+                    }),
+                ],
+                &[],
+            ),
+            output_schema: output_schema(workspace_properties()),
+            annotations: ToolAnnotations::read_only(false),
+        },
+        Tool {
+            name: "dependency_graph".to_string(),
+            description: "Resolved dependency graph as JSON nodes and edges, with edges labeled by dependency kind and features. Filters mirror cargo tree; `why` returns every path from the workspace members to a package".to_string(),
+            input_schema: object_schema(
+                &[
+                    common_properties(),
+                    json!({
+                        "package": {
+                            "type": "string",
+                            "description": "Start from this package (name or name@version) instead of the workspace members"
+                        },
+                        "features": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "description": "Features to activate when resolving"
+                        },
+                        "all_features": {
+                            "type": "boolean",
+                            "description": "Activate all available features"
+                        },
+                        "no_default_features": {
+                            "type": "boolean",
+                            "description": "Do not activate the default feature"
+                        },
+                        "target": {
+                            "type": "string",
+                            "description": "Only include dependencies for this target triple (or \"all\")"
+                        },
+                        "edges": {
+                            "type": "string",
+                            "description": "Dependency kinds to follow (normal, build, dev, all, no-normal, no-build, no-dev, no-proc-macro)"
+                        },
+                        "invert": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "description": "Show what depends on these packages"
+                        },
+                        "prune": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "description": "Do not expand the dependencies of these packages"
+                        },
+                        "depth": {
+                            "type": "integer",
+                            "description": "Maximum number of edges from the roots"
+                        },
+                        "duplicates": {
+                            "type": "boolean",
+                            "description": "Only packages resolved to several versions, and what depends on them"
+                        },
+                        "why": {
+                            "type": "string",
+                            "description": "Package (name or name@version) to explain: returns every path from the roots to it"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Maximum number of paths returned for why (default: 100)"
+                        }
+                    }),
+                ],
+                &[],
+            ),
+            output_schema: output_schema(dependency_graph_properties()),
+            annotations: ToolAnnotations::read_only(false),
+        },
+        Tool {
+            name: "duplicate_dependencies".to_string(),
+            description: "List packages resolved to more than one version, the direct dependencies pulling in each version, and whether `cargo update --precise` or bumping one dependency would unify them".to_string(),
+            input_schema: object_schema(
+                &[
+                    common_properties(),
+                    json!({
+                        "features": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "description": "Features to activate when resolving"
+                        },
+                        "all_features": {
+                            "type": "boolean",
+                            "description": "Activate all available features"
+                        },
+                        "no_default_features": {
+                            "type": "boolean",
+                            "description": "Do not activate the default feature"
+                        },
+                        "target": {
+                            "type": "string",
+                            "description": "Only include dependencies for this target triple (or \"all\")"
+                        },
+                        "edges": {
+                            "type": "string",
+                            "description": "Dependency kinds to consider, e.g. no-dev (normal, build, dev, all, no-normal, no-build, no-dev, no-proc-macro)"
+                        }
+                    }),
+                ],
+                &[],
+            ),
+            output_schema: output_schema(duplicates_properties()),
+            annotations: ToolAnnotations::read_only(false),
+        },
+        Tool {
+            name: "outdated".to_string(),
+            description: "Compare the dependencies in Cargo.lock with the newest versions in the local registry index cache, listing compatible and semver-incompatible updates per workspace member. Works offline".to_string(),
+            input_schema: object_schema(
+                &[
+                    common_properties(),
+                    json!({
+                        "dependency": {
+                            "type": "string",
+                            "description": "Only check this dependency (name or name@version)"
+                        },
+                        "features": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "description": "Features to activate when resolving"
+                        },
+                        "all_features": {
+                            "type": "boolean",
+                            "description": "Activate all available features"
+                        },
+                        "no_default_features": {
+                            "type": "boolean",
+                            "description": "Do not activate the default feature"
+                        }
+                    }),
+                ],
+                &[],
+            ),
+            output_schema: output_schema(outdated_properties()),
+            annotations: ToolAnnotations::read_only(false),
+        },
+        Tool {
+            name: "audit".to_string(),
+            description: "Check every package in Cargo.lock against a local clone of the RustSec advisory database, reporting affected and patched versions, the dependency paths that pull each crate in, and the update or add_crate call that fixes it. Works offline".to_string(),
+            input_schema: object_schema(
+                &[
+                    common_properties(),
+                    json!({
+                        "advisory_db": {
+                            "type": "string",
+                            "description": "Path to the advisory-db checkout (defaults to the server setting, then $CARGO_HOME/advisory-db)"
+                        },
+                        "features": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "description": "Features to activate when resolving"
+                        },
+                        "all_features": {
+                            "type": "boolean",
+                            "description": "Activate all available features"
+                        },
+                        "no_default_features": {
+                            "type": "boolean",
+                            "description": "Do not activate the default feature"
+                        },
+                        "target": {
+                            "type": "string",
+                            "description": "Only include dependencies for this target triple (or \"all\")"
+                        }
+                    }),
+                ],
+                &[],
+            ),
+            output_schema: output_schema(audit_properties()),
+            annotations: ToolAnnotations::read_only(false),
         }
     ]
 }
@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tokio::sync::oneshot;
+
+/// Tools that edit manifests, lock files or the target directory. Calls to
+/// these on the same workspace run one at a time, in the order received.
+/// `coverage` clears the profiles it collects under the target directory
+/// and `bench` may save Criterion baselines there.
+pub fn is_mutating_tool(tool_name: &str) -> bool {
+    matches!(
+        tool_name,
+        "add_crate"
+            | "remove_crate"
+            | "clean"
+            | "update"
+            | "fmt"
+            | "new"
+            | "init"
+            | "apply_fixes"
+            | "coverage"
+            | "bench"
+    )
+}
+
+/// Resolve the directory that identifies a workspace: the nearest ancestor
+/// whose `Cargo.toml` has a `workspace` table, otherwise the nearest
+/// package root, otherwise the directory itself.
+pub fn workspace_key(working_directory: Option<&str>) -> PathBuf {
+    let start = working_directory
+        .map(PathBuf::from)
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_default();
+    let start = start.canonicalize().unwrap_or(start);
+
+    let mut package_root: Option<&Path> = None;
+    for dir in start.ancestors() {
+        let Ok(manifest) = std::fs::read_to_string(dir.join("Cargo.toml")) else {
+            continue;
+        };
+        if declares_workspace(&manifest) {
+            return dir.to_path_buf();
+        }
+        package_root.get_or_insert(dir);
+    }
+
+    package_root.unwrap_or(&start).to_path_buf()
+}
+
+/// Whether a manifest is a workspace root. Any `workspace` table counts,
+/// including one that only holds `[workspace.package]` or
+/// `[workspace.dependencies]`.
+fn declares_workspace(manifest: &str) -> bool {
+    toml::from_str::<toml::Table>(manifest)
+        .is_ok_and(|manifest| manifest.get("workspace").is_some_and(toml::Value::is_table))
+}
+
+/// FIFO queue of mutating operations, one chain per workspace.
+///
+/// Enqueueing is synchronous so the order of tickets matches the order in
+/// which requests were read, no matter how the tasks that hold them are
+/// scheduled afterwards.
+#[derive(Debug, Default)]
+pub struct WorkspaceQueue {
+    tails: Mutex<HashMap<PathBuf, oneshot::Receiver<()>>>,
+}
+
+impl WorkspaceQueue {
+    pub fn enqueue(&self, workspace: PathBuf) -> QueueTicket {
+        let (release, done) = oneshot::channel();
+        let previous = self
+            .tails
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(workspace, done);
+        QueueTicket {
+            previous,
+            release: Some(release),
+        }
+    }
+}
+
+/// A place in a workspace queue. The holder may run once every earlier
+/// ticket has been released.
+#[derive(Debug)]
+pub struct QueueTicket {
+    previous: Option<oneshot::Receiver<()>>,
+    release: Option<oneshot::Sender<()>>,
+}
+
+/// Releases the next ticket in the queue when dropped.
+#[derive(Debug)]
+pub struct QueueGuard {
+    _release: Option<oneshot::Sender<()>>,
+}
+
+impl QueueTicket {
+    pub async fn acquire(mut self) -> QueueGuard {
+        if let Some(previous) = self.previous.take() {
+            // The sender is dropped, never used, when the previous holder is
+            // done, so an error here is the normal wake-up.
+            let _ = previous.await;
+        }
+        QueueGuard {
+            _release: self.release.take(),
+        }
+    }
+}
+
+impl Drop for QueueTicket {
+    fn drop(&mut self) {
+        // A ticket abandoned while still waiting must not let the next one
+        // overtake the operation in front of it.
+        if let (Some(previous), Some(release)) = (self.previous.take(), self.release.take()) {
+            tokio::spawn(async move {
+                let _ = previous.await;
+                drop(release);
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn recognizes_workspace_tables() {
+        assert!(declares_workspace("[workspace]\nmembers = [\"a\"]\n"));
+        assert!(declares_workspace("[workspace] # root\n"));
+        assert!(declares_workspace(
+            "[workspace.package]\nedition = \"2021\"\n\n[workspace.dependencies]\nserde = \"1\"\n"
+        ));
+        assert!(!declares_workspace("[package]\nname = \"a\"\n"));
+        assert!(!declares_workspace("[package]\nworkspace = \"..\"\n"));
+        assert!(!declares_workspace("[workspace\n"));
+    }
+
+    #[test]
+    fn keys_members_by_their_workspace_root() {
+        let root = std::env::temp_dir().join(format!("cargo-mcp-queue-{}", std::process::id()));
+        let member = root.join("crates").join("member");
+        let nested = member.join("src").join("bin");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(
+            root.join("Cargo.toml"),
+            "[workspace.dependencies] # shared\nserde = \"1\"\n",
+        )
+        .unwrap();
+        std::fs::write(member.join("Cargo.toml"), "[package]\nname = \"member\"\n").unwrap();
+        let root = root.canonicalize().unwrap();
+
+        assert_eq!(workspace_key(nested.to_str()), root);
+        assert_eq!(workspace_key(member.to_str()), root);
+
+        // Without a workspace table, the nearest package is its own key.
+        std::fs::write(root.join("Cargo.toml"), "[package]\nname = \"root\"\n").unwrap();
+        assert_eq!(
+            workspace_key(nested.to_str()),
+            member.canonicalize().unwrap()
+        );
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn runs_tickets_in_enqueue_order() {
+        let queue = WorkspaceQueue::default();
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let tickets: Vec<QueueTicket> = (0..4)
+            .map(|_| queue.enqueue(PathBuf::from("/ws")))
+            .collect();
+        // Another workspace is not held up by the first one.
+        let other = queue.enqueue(PathBuf::from("/other"));
+        drop(other.acquire().await);
+
+        // Spawn in reverse so acquisition order cannot come from scheduling.
+        let mut handles = Vec::new();
+        for (i, ticket) in tickets.into_iter().enumerate().rev() {
+            let order = order.clone();
+            handles.push(tokio::spawn(async move {
+                let _guard = ticket.acquire().await;
+                tokio::task::yield_now().await;
+                order.lock().unwrap().push(i);
+            }));
+        }
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), [0, 1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn abandoned_ticket_keeps_its_place() {
+        let queue = WorkspaceQueue::default();
+        let first = queue.enqueue(PathBuf::from("/ws")).acquire().await;
+        let abandoned = queue.enqueue(PathBuf::from("/ws"));
+        let last = queue.enqueue(PathBuf::from("/ws"));
+        drop(abandoned);
+
+        let waiting = tokio::spawn(last.acquire());
+        tokio::task::yield_now().await;
+        assert!(!waiting.is_finished());
+        drop(first);
+        waiting.await.unwrap();
+    }
+}
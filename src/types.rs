@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::config::TestRunner;
 use crate::error::McpError;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -21,12 +22,68 @@ pub struct McpResponse {
     pub error: Option<McpError>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct McpNotification {
+    pub jsonrpc: String,
+    pub method: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<Value>,
+}
+
+/// Anything the server writes to stdout.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum McpMessage {
+    Response(McpResponse),
+    Notification(McpNotification),
+    Batch(Vec<McpResponse>),
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Tool {
     pub name: String,
     pub description: String,
     #[serde(rename = "inputSchema")]
     pub input_schema: Value,
+    #[serde(rename = "outputSchema", skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<ToolAnnotations>,
+}
+
+/// Behavior hints advertised to clients on protocol 2025-03-26 and later.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolAnnotations {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_only_hint: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub destructive_hint: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idempotent_hint: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub open_world_hint: Option<bool>,
+}
+
+impl ToolAnnotations {
+    /// The tool only inspects the project.
+    pub fn read_only(open_world: bool) -> Option<Self> {
+        Some(Self {
+            read_only_hint: Some(true),
+            open_world_hint: Some(open_world),
+            ..Self::default()
+        })
+    }
+
+    /// The tool changes files, build artifacts or installed binaries.
+    pub fn mutating(destructive: bool, idempotent: bool, open_world: bool) -> Option<Self> {
+        Some(Self {
+            read_only_hint: Some(false),
+            destructive_hint: Some(destructive),
+            idempotent_hint: Some(idempotent),
+            open_world_hint: Some(open_world),
+        })
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -123,8 +180,6 @@ pub struct CargoToolParams {
     #[serde(default)]
     pub test_threads: Option<u32>,
     #[serde(default)]
-    pub open: Option<bool>,
-    #[serde(default)]
     pub no_deps: Option<bool>,
     #[serde(default)]
     pub document_private_items: Option<bool>,
@@ -208,4 +263,54 @@ pub struct CargoToolParams {
     pub message_format: Option<String>,
     #[serde(default)]
     pub exclude: Option<Vec<String>>,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    #[serde(default)]
+    pub args: Option<Vec<String>>,
+    #[serde(default)]
+    pub check: Option<bool>,
+    #[serde(default)]
+    pub structured: Option<bool>,
+    #[serde(default)]
+    pub rerun_failed: Option<bool>,
+    #[serde(default)]
+    pub runner: Option<TestRunner>,
+    #[serde(default)]
+    pub iterations: Option<u32>,
+    #[serde(default)]
+    pub shuffle: Option<bool>,
+    #[serde(default)]
+    pub thread_counts: Option<Vec<u32>>,
+    #[serde(default)]
+    pub no_fail_fast: Option<bool>,
+    #[serde(default)]
+    pub doc: Option<bool>,
+    #[serde(default)]
+    pub file: Option<String>,
+    #[serde(default)]
+    pub baseline: Option<String>,
+    #[serde(default)]
+    pub save_baseline: Option<String>,
+    #[serde(default)]
+    pub regression_threshold: Option<f64>,
+    #[serde(default)]
+    pub diagnostic_ids: Option<Vec<String>>,
+    #[serde(default)]
+    pub code: Option<String>,
+    #[serde(default)]
+    pub explain_errors: Option<bool>,
+    #[serde(default)]
+    pub lint_groups: Option<Vec<String>>,
+    #[serde(default)]
+    pub allow: Option<Vec<String>>,
+    #[serde(default)]
+    pub warn: Option<Vec<String>>,
+    #[serde(default)]
+    pub deny: Option<Vec<String>>,
+    #[serde(default)]
+    pub workspace_lints_only: Option<bool>,
+    #[serde(default)]
+    pub why: Option<String>,
+    #[serde(default)]
+    pub advisory_db: Option<String>,
 }